@@ -0,0 +1,36 @@
+use crate::irq_id::IrqId;
+use crate::roots::Roots;
+use std::collections::HashMap;
+use std::fs;
+
+/// IRQ identifier -> CPU list as written by the kernel (e.g. `0-3,8`)
+pub type AffinityMap = HashMap<IrqId, String>;
+
+/// Get the configured affinity (`smp_affinity_list`) for all IRQs
+pub fn get_affinity_map(roots: &Roots) -> AffinityMap {
+    read_irq_attribute(roots, "smp_affinity_list")
+}
+
+/// Get the effective affinity (`effective_affinity_list`) for all IRQs
+pub fn get_effective_affinity_map(roots: &Roots) -> AffinityMap {
+    read_irq_attribute(roots, "effective_affinity_list")
+}
+
+/// Read one attribute file from every `/proc/irq/<n>` directory
+fn read_irq_attribute(roots: &Roots, file: &str) -> AffinityMap {
+    let irq_dir = roots.proc_path("irq");
+    let mut affinity_map = HashMap::new();
+
+    if let Ok(entries) = fs::read_dir(irq_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(irq) = path.file_name().and_then(|n| n.to_str())
+                && let Ok(irq) = irq.trim().parse::<u32>()
+                && let Ok(affinity) = fs::read_to_string(path.join(file))
+            {
+                affinity_map.insert(IrqId::Numeric(irq), affinity.trim().to_string());
+            }
+        }
+    }
+    affinity_map
+}
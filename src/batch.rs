@@ -0,0 +1,273 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use irqtop_rs::{
+    get_affinity_map, get_effective_affinity_map, influx, per_second, IrqFilter, IrqId, SampleRecord, Sampler,
+};
+
+use crate::csv::CsvWriter;
+use crate::http;
+
+use std::io::{self, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Record format for non-interactive output
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Plain-text table, like `top -b`
+    Text,
+    /// One pretty-printed JSON object per sample
+    Json,
+    /// One compact JSON object per line
+    Ndjson,
+    /// Comma-separated values, one row per IRQ per sample
+    Csv,
+    /// InfluxDB line protocol, one point per IRQ and CPU
+    Influx,
+}
+
+/// InfluxDB write endpoint that `--output influx` posts to instead of stdout
+pub struct InfluxTarget {
+    /// e.g. `http://localhost:8086/api/v2/write?org=lab&bucket=irq&precision=ns`
+    pub url: String,
+    /// Sent as `Authorization: Token <token>`
+    pub token: Option<String>,
+}
+
+/// Non-interactive mode settings
+pub struct BatchOptions {
+    pub interval: Duration,
+    /// Stop after this many samples; run until interrupted when `None`
+    pub iterations: Option<u64>,
+    /// Keep only the busiest N IRQs per sample; text defaults to 20, the
+    /// structured formats to every IRQ
+    pub top: Option<usize>,
+    pub format: OutputFormat,
+    /// Add one per-CPU delta column per CPU to CSV output
+    pub csv_wide: bool,
+    pub influx: Option<InfluxTarget>,
+    pub filter: IrqFilter,
+}
+
+/// Write one record per interval to stdout, without terminal control codes
+pub fn run(mut sampler: Sampler, opts: &BatchOptions) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut csv = CsvWriter::new(opts.csv_wide);
+
+    sampler.sample()?;
+    let mut iteration = 0;
+    while opts.iterations.is_none_or(|n| iteration < n) {
+        std::thread::sleep(opts.interval);
+        sampler.sample()?;
+        iteration += 1;
+
+        let affinity = get_affinity_map(sampler.roots());
+        let effective = get_effective_affinity_map(sampler.roots());
+        let mut record = SampleRecord::filtered(&sampler, &affinity, &effective, &opts.filter);
+        let top = match opts.format {
+            OutputFormat::Text => Some(opts.top.unwrap_or(20)),
+            _ => opts.top,
+        };
+        // 0 keeps every IRQ but still orders by rate
+        if let Some(n) = top {
+            record.retain_top(if n == 0 { usize::MAX } else { n });
+        }
+
+        if let (OutputFormat::Influx, Some(target)) = (opts.format, &opts.influx) {
+            // A collector should survive the database being briefly away
+            if let Err(err) = post_influx(target, &record) {
+                eprintln!("{:#}", err);
+            }
+            continue;
+        }
+
+        match write_record(&mut out, &record, opts.format, &mut csv) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+/// Per-CPU rates of one IRQ per interval, the non-interactive `show`
+pub fn run_show(mut sampler: Sampler, irq: &IrqId, opts: &BatchOptions) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    sampler.sample()?;
+    let mut iteration = 0;
+    while opts.iterations.is_none_or(|n| iteration < n) {
+        std::thread::sleep(opts.interval);
+        sampler.sample()?;
+        iteration += 1;
+
+        match write_irq_cpus(&mut out, &sampler, irq, &opts.filter) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+fn write_irq_cpus(out: &mut impl Write, sampler: &Sampler, irq: &IrqId, filter: &IrqFilter) -> io::Result<()> {
+    let (Some(stats), Some(delta)) = (sampler.current().get(irq), sampler.delta(irq)) else {
+        writeln!(out, "IRQ {} not found\n", irq)?;
+        return out.flush();
+    };
+    let elapsed = sampler.elapsed();
+    let total = filter.sum_selected(sampler.current(), stats, &delta.per_cpu);
+    writeln!(
+        out,
+        "irqtop-rs - {:.3} - IRQ {} ({}), interval {:.0}ms, {:.1}/s",
+        sampler.current().wall_clock.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+        irq,
+        stats.device(),
+        elapsed.as_secs_f64() * 1000.0,
+        per_second(total, elapsed)
+    )?;
+    // ERR/MIS have a single system-wide column
+    if !stats.is_global() {
+        writeln!(out, "{:>6} {:>12}", "CPU", "Rate/s")?;
+        for (idx, d) in delta.per_cpu.iter().enumerate() {
+            if !filter.keeps_column(sampler.current(), idx) {
+                continue;
+            }
+            writeln!(out, "{:>6} {:>12.1}", sampler.current().cpu_id(idx), per_second(*d, elapsed))?;
+        }
+    }
+    writeln!(out)?;
+    out.flush()
+}
+
+fn write_record(
+    out: &mut impl Write,
+    record: &SampleRecord,
+    format: OutputFormat,
+    csv: &mut CsvWriter,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => write_table(out, record)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, record)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            serde_json::to_writer(&mut *out, record)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => csv.write(out, record)?,
+        OutputFormat::Influx => influx::write_lines(out, record)?,
+    }
+    out.flush()
+}
+
+fn post_influx(target: &InfluxTarget, record: &SampleRecord) -> Result<()> {
+    let mut body = Vec::new();
+    influx::write_lines(&mut body, record)?;
+    let auth = target.token.as_ref().map(|t| format!("Token {}", t));
+    let mut headers = vec![("Content-Type", "text/plain; charset=utf-8")];
+    if let Some(auth) = &auth {
+        headers.push(("Authorization", auth));
+    }
+    http::post(&target.url, &headers, &body)
+}
+
+fn write_table(out: &mut impl Write, record: &SampleRecord) -> io::Result<()> {
+    writeln!(
+        out,
+        "irqtop-rs - {:.3} - interval {:.0}ms, {} IRQs, {} CPUs",
+        record.timestamp,
+        record.interval * 1000.0,
+        record.irqs.len(),
+        record.cpus.len()
+    )?;
+    writeln!(
+        out,
+        "{:>6} {:>12} {:<12} {:<13} {:<20} {:>8} {:<8} Device",
+        "IRQ", "Rate/s", "Affinity", "Eff. Affinity", "Chip", "HWIRQ", "Trigger"
+    )?;
+
+    let default_str = "N/A";
+    for irq in &record.irqs {
+        writeln!(
+            out,
+            "{:>6} {:>12.1} {:<12} {:<13} {:<20} {:>8} {:<8} {}",
+            irq.irq.to_string(),
+            irq.rate,
+            irq.affinity.as_deref().unwrap_or(default_str),
+            irq.effective_affinity.as_deref().unwrap_or(default_str),
+            irq.chip,
+            irq.hwirq.map(|h| h.to_string()).unwrap_or_default(),
+            irq.trigger,
+            irq.device
+        )?;
+    }
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::mock::serve_once;
+    use irqtop_rs::{IrqCategory, IrqRecord};
+
+    #[test]
+    fn posts_line_protocol_to_influx() {
+        let record = SampleRecord {
+            timestamp: 1.5,
+            wall_clock: UNIX_EPOCH + Duration::from_millis(1500),
+            interval: 1.0,
+            cpus: vec![0, 2],
+            irqs: vec![IrqRecord {
+                irq: IrqId::Numeric(24),
+                category: IrqCategory::Device,
+                device: "nvme0q1".to_string(),
+                chip: "IR-PCI-MSI".to_string(),
+                hwirq: Some(524288),
+                trigger: "edge".to_string(),
+                actions: vec!["nvme0q1".to_string()],
+                total: 150,
+                delta: 8,
+                rate: 8.0,
+                per_cpu: vec![3, 5],
+                counts: vec![100, 50],
+                affinity: None,
+                effective_affinity: None,
+            }],
+        };
+        let (url, server) = serve_once("204 No Content", "");
+        let target = InfluxTarget {
+            url: format!("{}/api/v2/write?org=lab&bucket=irq&precision=ns", url),
+            token: Some("secret".to_string()),
+        };
+        post_influx(&target, &record).unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(request.request_line, "POST /api/v2/write?org=lab&bucket=irq&precision=ns HTTP/1.1");
+        assert!(request.headers.contains(&"Authorization: Token secret".to_string()));
+        assert!(request.headers.contains(&"Content-Type: text/plain; charset=utf-8".to_string()));
+        assert_eq!(
+            String::from_utf8(request.body).unwrap(),
+            "interrupts,irq=24,device=nvme0q1,cpu=0,chip=IR-PCI-MSI count=100i,rate=3 1500000000\n\
+             interrupts,irq=24,device=nvme0q1,cpu=2,chip=IR-PCI-MSI count=50i,rate=5 1500000000\n"
+        );
+    }
+
+    #[test]
+    fn influx_errors_surface_the_status() {
+        let (url, server) = serve_once("400 Bad Request", "bad line");
+        let target = InfluxTarget { url, token: None };
+        let record = SampleRecord {
+            timestamp: 0.0,
+            wall_clock: UNIX_EPOCH,
+            interval: 1.0,
+            cpus: Vec::new(),
+            irqs: Vec::new(),
+        };
+        let err = post_influx(&target, &record).unwrap_err().to_string();
+        let request = server.join().unwrap();
+
+        assert!(!request.headers.iter().any(|h| h.starts_with("Authorization")));
+        assert!(err.contains("400 Bad Request") && err.contains("bad line"), "{}", err);
+    }
+}
@@ -0,0 +1,536 @@
+//! Capture files: a timestamped stream of raw `/proc/interrupts` reads
+//! plus IRQ affinity, for recording on one machine and analysing later.
+//!
+//! # Format, version 1
+//!
+//! A capture is a single gzip stream. All integers are little-endian.
+//! The decompressed stream starts with an 8 byte header:
+//!
+//! | Bytes | Content                          |
+//! |-------|----------------------------------|
+//! | 6     | magic `IRQCAP`                   |
+//! | 2     | format version, `u16`, currently 1 |
+//!
+//! followed by any number of frames, one per sample:
+//!
+//! | Bytes | Content                                                        |
+//! |-------|----------------------------------------------------------------|
+//! | 1     | frame type, `1` = snapshot                                     |
+//! | 8     | wall-clock time, `u64` nanoseconds since the Unix epoch        |
+//! | 8     | monotonic time since the first frame, `u64` nanoseconds        |
+//! | 4 + n | `u32` length, then the unmodified contents of `/proc/interrupts` |
+//! | 4 + n | `u32` length, then the affinity table                          |
+//!
+//! The affinity table is UTF-8 text with one line per IRQ directory under
+//! `/proc/irq`: `<irq> <smp_affinity_list> <effective_affinity_list>\n`,
+//! with `-` for a list that could not be read.
+//!
+//! The writer flushes the compressor after every frame, so a capture cut
+//! short by a crash or Ctrl-C is readable up to its last complete frame.
+//! Readers reject versions they do not know and stop at unknown frame types.
+//! The stream can't be seeked, but [`CaptureReader::next_info`] indexes
+//! where each frame starts so a reader can later skip forward to it.
+//!
+//! # Text snapshots
+//!
+//! A single frame can also be written as plain text with [`write_text`],
+//! for diffing before/after states with [`read_text`]:
+//!
+//! ```text
+//! # irqtop-rs snapshot 1
+//! # 2024-05-01T12:00:00.000000000Z
+//! <unmodified /proc/interrupts>
+//! # affinity
+//! <affinity table>
+//! ```
+
+use crate::affinity::AffinityMap;
+use crate::interrupts::{parse_interrupts_into, Snapshot};
+use crate::irq_id::IrqId;
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const MAGIC: &[u8; 6] = b"IRQCAP";
+pub const VERSION: u16 = 1;
+
+const TEXT_HEADER: &str = "# irqtop-rs snapshot 1";
+const TEXT_AFFINITY: &str = "# affinity";
+
+const FRAME_SNAPSHOT: u8 = 1;
+/// Refuse length prefixes beyond this, rather than allocating for a corrupt file
+const MAX_BLOB: usize = 256 * 1024 * 1024;
+
+/// One recorded sample
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub wall_clock: SystemTime,
+    /// Monotonic time since the capture's first frame
+    pub offset: Duration,
+    /// Raw `/proc/interrupts` contents
+    pub interrupts: Vec<u8>,
+    pub affinity: AffinityMap,
+    pub effective_affinity: AffinityMap,
+}
+
+impl Frame {
+    /// Parse the recorded `/proc/interrupts` into `snapshot`, stamped with
+    /// the recorded wall-clock time and with `origin + offset` as its
+    /// monotonic time, so deltas between frames use the recorded intervals
+    pub fn snapshot_into(&self, snapshot: &mut Snapshot, origin: Instant) {
+        parse_interrupts_into(&self.interrupts, snapshot);
+        snapshot.taken_at = origin + self.offset;
+        snapshot.wall_clock = self.wall_clock;
+    }
+}
+
+/// Writes frames to a capture stream
+pub struct CaptureWriter<W: Write> {
+    out: GzEncoder<W>,
+    started: Option<Instant>,
+    table: String,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Write the header; frames follow with [`write_frame`](CaptureWriter::write_frame)
+    pub fn new(out: W) -> io::Result<Self> {
+        let mut out = GzEncoder::new(out, Compression::default());
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        Ok(Self {
+            out,
+            started: None,
+            table: String::new(),
+        })
+    }
+
+    /// Append one sample read at `taken_at` / `wall_clock`
+    pub fn write_frame(
+        &mut self,
+        taken_at: Instant,
+        wall_clock: SystemTime,
+        interrupts: &[u8],
+        affinity: &AffinityMap,
+        effective_affinity: &AffinityMap,
+    ) -> io::Result<()> {
+        let started = *self.started.get_or_insert(taken_at);
+        let offset = taken_at.saturating_duration_since(started);
+        let wall = wall_clock.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        self.table.clear();
+        format_affinity(&mut self.table, affinity, effective_affinity);
+
+        self.out.write_all(&[FRAME_SNAPSHOT])?;
+        self.out.write_all(&(wall.as_nanos() as u64).to_le_bytes())?;
+        self.out.write_all(&(offset.as_nanos() as u64).to_le_bytes())?;
+        write_blob(&mut self.out, interrupts)?;
+        write_blob(&mut self.out, self.table.as_bytes())?;
+        self.out.flush()
+    }
+
+    /// Write the gzip trailer and return the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        self.out.finish()
+    }
+}
+
+fn write_blob(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(data)
+}
+
+/// Where a frame sits in a capture, from [`CaptureReader::next_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub wall_clock: SystemTime,
+    /// Monotonic time since the capture's first frame
+    pub offset: Duration,
+    /// Start of the frame in the decompressed stream, for
+    /// [`CaptureReader::skip_to`]
+    pub position: u64,
+}
+
+/// Reads frames from a capture stream
+pub struct CaptureReader<R: Read> {
+    input: GzDecoder<R>,
+    /// Bytes of the decompressed stream consumed so far
+    position: u64,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Check the header and version
+    pub fn new(input: R) -> Result<Self> {
+        let mut input = GzDecoder::new(input);
+        let mut header = [0; 8];
+        input.read_exact(&mut header).context("not a capture file")?;
+        if &header[..6] != MAGIC {
+            bail!("not a capture file");
+        }
+        let version = u16::from_le_bytes([header[6], header[7]]);
+        if version != VERSION {
+            bail!("unsupported capture version {} (expected {})", version, VERSION);
+        }
+        Ok(Self {
+            input,
+            position: header.len() as u64,
+        })
+    }
+
+    /// Position of the next frame in the decompressed stream
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Next frame, or `None` at the end of the capture.
+    ///
+    /// A frame cut off by the end of the file counts as the end, since that
+    /// is how a recording interrupted mid-write looks.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>> {
+        match self.read_frame() {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            res => Ok(res?),
+        }
+    }
+
+    /// Like [`next_frame`](CaptureReader::next_frame), but skip over the
+    /// frame's contents and return only its times and position, for indexing
+    /// a capture without holding it in memory
+    pub fn next_info(&mut self) -> Result<Option<FrameInfo>> {
+        match self.skip_frame() {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            res => Ok(res?),
+        }
+    }
+
+    /// Skip ahead to `position`, e.g. a [`FrameInfo::position`], so that the
+    /// next read returns the frame there. The stream can't go backwards; to
+    /// reach an earlier frame, open a new reader.
+    pub fn skip_to(&mut self, position: u64) -> io::Result<()> {
+        let Some(len) = position.checked_sub(self.position) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot seek backwards in a capture"));
+        };
+        self.skip(len)
+    }
+
+    /// Read every remaining frame
+    pub fn read_all(mut self) -> Result<Vec<Frame>> {
+        let mut frames = Vec::new();
+        while let Some(frame) = self.next_frame()? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        let Some(info) = self.read_header()? else {
+            return Ok(None);
+        };
+        let interrupts = self.read_blob()?;
+        let table = self.read_blob()?;
+
+        let (affinity, effective_affinity) = parse_affinity(String::from_utf8_lossy(&table).lines());
+
+        Ok(Some(Frame {
+            wall_clock: info.wall_clock,
+            offset: info.offset,
+            interrupts,
+            affinity,
+            effective_affinity,
+        }))
+    }
+
+    fn skip_frame(&mut self) -> io::Result<Option<FrameInfo>> {
+        let Some(info) = self.read_header()? else {
+            return Ok(None);
+        };
+        for _ in 0..2 {
+            let len = self.read_len()?;
+            self.skip(len as u64)?;
+        }
+        Ok(Some(info))
+    }
+
+    /// Frame type and times, or `None` at the end or an unknown frame type
+    fn read_header(&mut self) -> io::Result<Option<FrameInfo>> {
+        let position = self.position;
+        let mut kind = [0; 1];
+        if self.input.read(&mut kind)? == 0 {
+            return Ok(None);
+        }
+        self.position += 1;
+        if kind[0] != FRAME_SNAPSHOT {
+            return Ok(None);
+        }
+        let wall = self.read_u64()?;
+        let offset = self.read_u64()?;
+        Ok(Some(FrameInfo {
+            wall_clock: UNIX_EPOCH + Duration::from_nanos(wall),
+            offset: Duration::from_nanos(offset),
+            position,
+        }))
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.input.read_exact(buf)?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_len(&mut self) -> io::Result<usize> {
+        let mut len = [0; 4];
+        self.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_BLOB {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt capture frame"));
+        }
+        Ok(len)
+    }
+
+    fn read_blob(&mut self) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; self.read_len()?];
+        self.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Decompress and drop `len` bytes
+    fn skip(&mut self, len: u64) -> io::Result<()> {
+        let skipped = io::copy(&mut (&mut self.input).take(len), &mut io::sink())?;
+        self.position += skipped;
+        if skipped < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+}
+
+/// Write one frame as a text snapshot
+pub fn write_text(out: &mut impl Write, frame: &Frame) -> io::Result<()> {
+    writeln!(out, "{}", TEXT_HEADER)?;
+    writeln!(out, "# {}", humantime::format_rfc3339_nanos(frame.wall_clock))?;
+    out.write_all(&frame.interrupts)?;
+    if !frame.interrupts.ends_with(b"\n") {
+        writeln!(out)?;
+    }
+    writeln!(out, "{}", TEXT_AFFINITY)?;
+    let mut table = String::new();
+    format_affinity(&mut table, &frame.affinity, &frame.effective_affinity);
+    out.write_all(table.as_bytes())
+}
+
+/// Read a text snapshot written by [`write_text`]
+pub fn read_text(input: impl BufRead) -> Result<Frame> {
+    let mut lines = input.lines();
+    if lines.next().transpose()?.as_deref() != Some(TEXT_HEADER) {
+        bail!("not an irqtop-rs snapshot");
+    }
+    let time = lines.next().transpose()?.unwrap_or_default();
+    let wall_clock = humantime::parse_rfc3339(time.trim_start_matches('#').trim())
+        .with_context(|| format!("bad snapshot time {:?}", time))?;
+
+    let mut interrupts = Vec::new();
+    for line in lines.by_ref() {
+        let line = line?;
+        if line == TEXT_AFFINITY {
+            break;
+        }
+        interrupts.extend_from_slice(line.as_bytes());
+        interrupts.push(b'\n');
+    }
+    let table = lines.collect::<io::Result<Vec<_>>>()?;
+    let (affinity, effective_affinity) = parse_affinity(table.iter().map(String::as_str));
+
+    Ok(Frame {
+        wall_clock,
+        offset: Duration::ZERO,
+        interrupts,
+        affinity,
+        effective_affinity,
+    })
+}
+
+/// One `<irq> <affinity> <effective>` line per IRQ in either map
+fn format_affinity(out: &mut String, affinity: &AffinityMap, effective_affinity: &AffinityMap) {
+    let mut irqs: Vec<&IrqId> = affinity.keys().chain(effective_affinity.keys()).collect();
+    irqs.sort();
+    irqs.dedup();
+    for irq in irqs {
+        let _ = writeln!(
+            out,
+            "{} {} {}",
+            irq,
+            affinity.get(irq).map_or("-", String::as_str),
+            effective_affinity.get(irq).map_or("-", String::as_str)
+        );
+    }
+}
+
+fn parse_affinity<'a>(lines: impl Iterator<Item = &'a str>) -> (AffinityMap, AffinityMap) {
+    let mut affinity = AffinityMap::new();
+    let mut effective_affinity = AffinityMap::new();
+    for line in lines {
+        let mut fields = line.split_whitespace();
+        let (Some(irq), Some(aff), Some(eff)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let irq = IrqId::parse(irq);
+        if aff != "-" {
+            affinity.insert(irq.clone(), aff.to_string());
+        }
+        if eff != "-" {
+            effective_affinity.insert(irq, eff.to_string());
+        }
+    }
+    (affinity, effective_affinity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+
+    const INTERRUPTS: &[u8] = b"           CPU0       CPU1\n 24:        100         50   IR-PCI-MSI 524288-edge      nvme0q1\nERR:          0\n";
+
+    fn affinity(irq: u32, cpus: &str) -> AffinityMap {
+        AffinityMap::from([(IrqId::Numeric(irq), cpus.to_string())])
+    }
+
+    /// A capture of `n` frames taken 100ms apart
+    fn capture(n: usize) -> Vec<u8> {
+        let start = Instant::now();
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for i in 0..n {
+            let offset = Duration::from_millis(100 * i as u64);
+            let interrupts = String::from_utf8_lossy(INTERRUPTS).replace("100", &format!("{:03}", 100 + i));
+            writer
+                .write_frame(
+                    start + offset,
+                    UNIX_EPOCH + Duration::from_secs(1_700_000_000) + offset,
+                    interrupts.as_bytes(),
+                    &affinity(24, "0-1"),
+                    &affinity(24, &i.to_string()),
+                )
+                .unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    /// Decompressed contents of a capture
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut raw).unwrap();
+        raw
+    }
+
+    fn compress(raw: &[u8]) -> Vec<u8> {
+        let mut out = GzEncoder::new(Vec::new(), Compression::default());
+        out.write_all(raw).unwrap();
+        out.finish().unwrap()
+    }
+
+    #[test]
+    fn round_trips_frames() {
+        let frames = CaptureReader::new(&capture(3)[..]).unwrap().read_all().unwrap();
+        assert_eq!(frames.len(), 3);
+        for (i, frame) in frames.iter().enumerate() {
+            let offset = Duration::from_millis(100 * i as u64);
+            assert_eq!(frame.offset, offset);
+            assert_eq!(frame.wall_clock, UNIX_EPOCH + Duration::from_secs(1_700_000_000) + offset);
+            assert_eq!(frame.affinity, affinity(24, "0-1"));
+            assert_eq!(frame.effective_affinity, affinity(24, &i.to_string()));
+
+            let mut snapshot = Snapshot::default();
+            let origin = Instant::now();
+            frame.snapshot_into(&mut snapshot, origin);
+            assert_eq!(snapshot.taken_at, origin + offset);
+            assert_eq!(snapshot.get(&IrqId::Numeric(24)).unwrap().counts, [100 + i as u64, 50]);
+        }
+    }
+
+    #[test]
+    fn reads_truncated_capture_up_to_last_complete_frame() {
+        let raw = decompress(&capture(3));
+        let frame_len = (raw.len() - 8) / 3;
+        // Cut inside the third frame, and exactly after the second
+        for cut in [raw.len() - 5, 8 + 2 * frame_len] {
+            let frames = CaptureReader::new(&compress(&raw[..cut])[..]).unwrap().read_all().unwrap();
+            assert_eq!(frames.len(), 2, "cut at {}", cut);
+        }
+    }
+
+    #[test]
+    fn indexes_frames_and_skips_to_them() {
+        let data = capture(3);
+        let mut reader = CaptureReader::new(&data[..]).unwrap();
+        let mut index = Vec::new();
+        while let Some(info) = reader.next_info().unwrap() {
+            index.push(info);
+        }
+        assert_eq!(index.len(), 3);
+        assert_eq!(index[0].position, 8);
+        assert_eq!(index[2].offset, Duration::from_millis(200));
+
+        let mut reader = CaptureReader::new(&data[..]).unwrap();
+        reader.skip_to(index[1].position).unwrap();
+        let frame = reader.next_frame().unwrap().unwrap();
+        assert_eq!(frame.offset, index[1].offset);
+        assert_eq!(reader.position(), index[2].position);
+        assert!(reader.skip_to(index[0].position).is_err());
+
+        // A frame cut short ends the index like it ends `read_all`
+        let raw = decompress(&data);
+        let cut = compress(&raw[..raw.len() - 5]);
+        let mut reader = CaptureReader::new(&cut[..]).unwrap();
+        assert!(reader.next_info().unwrap().is_some());
+        assert!(reader.next_info().unwrap().is_some());
+        assert!(reader.next_info().unwrap().is_none());
+    }
+
+    #[test]
+    fn reads_unterminated_gzip_stream() {
+        // A writer killed before `finish` leaves no gzip trailer
+        let mut data = capture(2);
+        data.truncate(data.len() - 8);
+        let frames = CaptureReader::new(&data[..]).unwrap().read_all().unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_version() {
+        let mut raw = decompress(&capture(1));
+        raw[0] = b'X';
+        let err = CaptureReader::new(&compress(&raw)[..]).err().unwrap();
+        assert!(err.to_string().contains("not a capture file"));
+
+        let mut raw = decompress(&capture(1));
+        raw[6..8].copy_from_slice(&99u16.to_le_bytes());
+        let err = CaptureReader::new(&compress(&raw)[..]).err().unwrap();
+        assert!(err.to_string().contains("unsupported capture version 99"));
+
+        assert!(CaptureReader::new(&b"not gzip"[..]).is_err());
+    }
+
+    #[test]
+    fn round_trips_text_snapshot() {
+        let frame = CaptureReader::new(&capture(1)[..]).unwrap().read_all().unwrap().remove(0);
+        let mut text = Vec::new();
+        write_text(&mut text, &frame).unwrap();
+        let read = read_text(&text[..]).unwrap();
+
+        assert_eq!(read.wall_clock, frame.wall_clock);
+        assert_eq!(read.interrupts, frame.interrupts);
+        assert_eq!(read.affinity, frame.affinity);
+        assert_eq!(read.effective_affinity, frame.effective_affinity);
+        assert!(read_text(&b"garbage\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use irqtop_rs::{IrqCategory, SampleRecord};
+
+use std::io::{self, Write};
+
+/// CSV writer with a fixed column schema:
+/// `timestamp,irq,device,delta,rate,affinity,effective_affinity`.
+///
+/// The wide layout appends one per-CPU delta column per CPU, named after
+/// the CPU ids in the first record's header. Global error rows (`ERR`,
+/// `MIS`) have no per-CPU breakdown and leave those columns empty.
+pub struct CsvWriter {
+    wide: bool,
+    /// CPU ids of the wide columns, fixed by the first record
+    cpus: Option<Vec<u32>>,
+}
+
+impl CsvWriter {
+    pub fn new(wide: bool) -> Self {
+        Self { wide, cpus: None }
+    }
+
+    pub fn write(&mut self, out: &mut impl Write, record: &SampleRecord) -> io::Result<()> {
+        let cpus = match &self.cpus {
+            Some(cpus) => cpus,
+            None => {
+                write!(out, "timestamp,irq,device,delta,rate,affinity,effective_affinity")?;
+                if self.wide {
+                    for cpu in &record.cpus {
+                        write!(out, ",cpu{}", cpu)?;
+                    }
+                }
+                writeln!(out)?;
+                self.cpus.insert(record.cpus.clone())
+            }
+        };
+
+        for irq in &record.irqs {
+            write!(
+                out,
+                "{:.3},{},{},{},{:.3},{},{}",
+                record.timestamp,
+                field(&irq.irq.to_string()),
+                field(&irq.device),
+                irq.delta,
+                irq.rate,
+                field(irq.affinity.as_deref().unwrap_or("")),
+                field(irq.effective_affinity.as_deref().unwrap_or(""))
+            )?;
+            if self.wide && irq.category == IrqCategory::Error {
+                for _ in cpus {
+                    write!(out, ",")?;
+                }
+            } else if self.wide {
+                // Match columns by CPU id in case CPUs went on- or offline
+                for cpu in cpus {
+                    match record.cpus.iter().position(|c| c == cpu).and_then(|i| irq.per_cpu.get(i)) {
+                        Some(delta) => write!(out, ",{}", delta)?,
+                        None => write!(out, ",")?,
+                    }
+                }
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote a field if it contains a separator, quote or line break
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irqtop_rs::{IrqId, IrqRecord};
+    use std::time::UNIX_EPOCH;
+
+    fn irq(id: &str, category: IrqCategory, per_cpu: Vec<u64>) -> IrqRecord {
+        let delta = per_cpu.iter().sum();
+        IrqRecord {
+            irq: IrqId::parse(id),
+            category,
+            device: "dev".to_string(),
+            chip: String::new(),
+            hwirq: None,
+            trigger: String::new(),
+            actions: Vec::new(),
+            total: delta,
+            delta,
+            rate: delta as f64,
+            counts: per_cpu.clone(),
+            per_cpu,
+            affinity: None,
+            effective_affinity: None,
+        }
+    }
+
+    #[test]
+    fn wide_layout_leaves_error_rows_without_cpu_cells() {
+        let record = SampleRecord {
+            timestamp: 1.0,
+            wall_clock: UNIX_EPOCH,
+            interval: 1.0,
+            cpus: vec![0, 2],
+            irqs: vec![
+                irq("24", IrqCategory::Device, vec![3, 5]),
+                irq("ERR", IrqCategory::Error, vec![4]),
+            ],
+        };
+        let mut out = Vec::new();
+        CsvWriter::new(true).write(&mut out, &record).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "timestamp,irq,device,delta,rate,affinity,effective_affinity,cpu0,cpu2");
+        assert_eq!(lines[1], "1.000,24,dev,8,8.000,,,3,5");
+        assert_eq!(lines[2], "1.000,ERR,dev,4,4.000,,,,");
+    }
+}
@@ -0,0 +1,124 @@
+use crate::interrupts::Snapshot;
+use crate::irq_id::IrqId;
+use std::time::Duration;
+
+/// Counter change for one IRQ between two snapshots
+#[derive(Debug, Clone)]
+pub struct IrqDelta {
+    pub irq: IrqId,
+    /// Sum of the per-CPU deltas
+    pub total: u64,
+    /// `total` per second of actual time elapsed between the snapshots
+    pub rate: f64,
+    /// Per-CPU deltas, in the same column order as the new snapshot's
+    /// [`IrqStats::counts`](crate::IrqStats::counts)
+    pub per_cpu: Vec<u64>,
+}
+
+/// Compute deltas for every IRQ present in both snapshots.
+///
+/// Per-CPU columns are matched by CPU id, so a CPU going offline or coming
+/// online between the snapshots doesn't shift the others; a CPU missing
+/// from the old snapshot has no baseline and counts as zero. Counters that
+/// went backwards count as zero too.
+pub fn calculate_delta(old: &Snapshot, new: &Snapshot) -> Vec<IrqDelta> {
+    let mut deltas = Vec::with_capacity(new.len());
+    calculate_delta_into(old, new, &mut deltas);
+    deltas
+}
+
+/// Like [`calculate_delta`], but reuses the entries already in `deltas`.
+///
+/// Entries are produced in `new`'s file order, so when the set of IRQs is
+/// unchanged since the last call every entry is overwritten in place and
+/// nothing is allocated.
+pub fn calculate_delta_into(old: &Snapshot, new: &Snapshot, deltas: &mut Vec<IrqDelta>) {
+    let elapsed = new.elapsed_since(old);
+    let mut len = 0;
+    for (irq, new_stats) in new.iter() {
+        let Some(old_stats) = old.get(irq) else {
+            continue;
+        };
+        if deltas.get(len).is_none_or(|d| &d.irq != irq) {
+            deltas.truncate(len);
+            deltas.push(IrqDelta {
+                irq: irq.clone(),
+                total: 0,
+                rate: 0.0,
+                per_cpu: Vec::with_capacity(new_stats.counts.len()),
+            });
+        }
+        let delta = &mut deltas[len];
+        delta.per_cpu.clear();
+        if old.cpus == new.cpus || new_stats.is_global() {
+            delta.per_cpu.extend(
+                new_stats.counts.iter()
+                    .zip(old_stats.counts.iter())
+                    .map(|(n, o)| n.saturating_sub(*o)),
+            );
+        } else {
+            delta.per_cpu.extend(new_stats.counts.iter().enumerate().map(|(idx, n)| {
+                let cpu = new.cpu_id(idx);
+                (0..old_stats.counts.len())
+                    .find(|&j| old.cpu_id(j) == cpu)
+                    .map_or(0, |j| n.saturating_sub(old_stats.counts[j]))
+            }));
+        }
+        delta.total = delta.per_cpu.iter().sum();
+        delta.rate = per_second(delta.total, elapsed);
+        len += 1;
+    }
+    deltas.truncate(len);
+}
+
+/// Scale a counter delta observed over `elapsed` to events per second
+pub fn per_second(delta: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 { delta as f64 / secs } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interrupts::parse_interrupts;
+
+    fn delta_of(old: &[u8], new: &[u8], irq: &str) -> IrqDelta {
+        let deltas = calculate_delta(&parse_interrupts(old), &parse_interrupts(new));
+        deltas.into_iter().find(|d| d.irq == IrqId::parse(irq)).unwrap()
+    }
+
+    #[test]
+    fn subtracts_per_cpu() {
+        let delta = delta_of(
+            b"  CPU0  CPU1\n 24:  100  500  IO-APIC 2-edge  a\n",
+            b"  CPU0  CPU1\n 24:  150  700  IO-APIC 2-edge  a\n",
+            "24",
+        );
+        assert_eq!(delta.per_cpu, [50, 200]);
+        assert_eq!(delta.total, 250);
+    }
+
+    #[test]
+    fn matches_columns_by_cpu_id_when_cpus_change() {
+        // CPU1 went offline and CPU3 came online
+        let delta = delta_of(
+            b"  CPU0  CPU1  CPU2\n 24:  100  500  1000  IO-APIC 2-edge  a\n",
+            b"  CPU0  CPU2  CPU3\n 24:  100  1300  40  IO-APIC 2-edge  a\n",
+            "24",
+        );
+        assert_eq!(delta.per_cpu, [0, 300, 0]);
+        assert_eq!(delta.total, 300);
+    }
+
+    #[test]
+    fn keeps_global_rows_whole() {
+        let delta = delta_of(b"  CPU0  CPU1\nERR:  3\n", b"  CPU1\nERR:  7\n", "ERR");
+        assert_eq!(delta.per_cpu, [4]);
+    }
+
+    #[test]
+    fn counters_going_backwards_count_as_zero() {
+        let delta = delta_of(b"  CPU0\n 24:  100  IO-APIC 2-edge  a\n", b"  CPU0\n 24:  10  IO-APIC 2-edge  a\n", "24");
+        assert_eq!(delta.total, 0);
+    }
+}
@@ -0,0 +1,127 @@
+/// Structured form of the text after the per-CPU counters, e.g.
+/// `IR-PCI-MSI 524288-edge      nvme0q1` or `GICv3  27 Level     arch_timer`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IrqDesc {
+    /// Interrupt controller (irq_chip) name, e.g. `IO-APIC`, `IR-PCI-MSI`
+    pub chip: String,
+    /// Hardware IRQ number within the chip's domain
+    pub hwirq: Option<u64>,
+    /// Flow handler name, lower-cased: `edge`, `level`, `fasteoi`, ...
+    pub trigger: String,
+    /// Handler actions (usually driver or device names) in registration order
+    pub actions: Vec<String>,
+}
+
+impl IrqDesc {
+    /// Split the trailing text of a numeric IRQ row into its parts.
+    ///
+    /// Handles the current x86 layout (`chip hwirq-trigger actions`), the
+    /// ARM layout (`chip hwirq Trigger actions`) and the older x86 layout
+    /// without a hwirq (`chip-trigger actions`).
+    pub fn parse(text: &str) -> Self {
+        let mut rest = text.trim();
+        let mut desc = IrqDesc::default();
+
+        let (chip, tail) = split_token(rest);
+        desc.chip = chip.to_string();
+        rest = tail;
+
+        let (tok, tail) = split_token(rest);
+        if let Some((hwirq, trigger)) = tok.split_once('-')
+            && let Ok(hwirq) = hwirq.parse()
+            && is_trigger(trigger)
+        {
+            // x86: `524288-edge`
+            desc.hwirq = Some(hwirq);
+            desc.trigger = trigger.to_ascii_lowercase();
+            rest = tail;
+        } else if let Ok(hwirq) = tok.parse() {
+            // ARM: `27 Level`
+            desc.hwirq = Some(hwirq);
+            rest = tail;
+            let (tok, tail) = split_token(rest);
+            if is_trigger(tok) {
+                desc.trigger = tok.to_ascii_lowercase();
+                rest = tail;
+            }
+        } else if let Some((chip, trigger)) = desc.chip.rsplit_once('-')
+            && is_trigger(trigger)
+        {
+            // Old x86: `IO-APIC-edge`
+            desc.trigger = trigger.to_ascii_lowercase();
+            desc.chip = chip.to_string();
+        }
+
+        desc.actions = rest
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(str::to_string)
+            .collect();
+        desc
+    }
+}
+
+fn split_token(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.find(char::is_whitespace) {
+        Some(end) => (&s[..end], s[end..].trim_start()),
+        None => (s, ""),
+    }
+}
+
+/// Flow handler names the kernel prints (`handle_<name>_irq`)
+fn is_trigger(tok: &str) -> bool {
+    matches!(
+        tok.to_ascii_lowercase().as_str(),
+        "edge" | "level" | "fasteoi" | "simple" | "percpu" | "percpu_devid" | "edge-eoi" | "untracked" | "nested"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_x86_layout() {
+        let desc = IrqDesc::parse("IR-PCI-MSI 524288-edge      nvme0q1");
+        assert_eq!(desc.chip, "IR-PCI-MSI");
+        assert_eq!(desc.hwirq, Some(524288));
+        assert_eq!(desc.trigger, "edge");
+        assert_eq!(desc.actions, ["nvme0q1"]);
+    }
+
+    #[test]
+    fn parses_arm_layout() {
+        let desc = IrqDesc::parse("GICv3  27 Level     arch_timer");
+        assert_eq!(desc.chip, "GICv3");
+        assert_eq!(desc.hwirq, Some(27));
+        assert_eq!(desc.trigger, "level");
+        assert_eq!(desc.actions, ["arch_timer"]);
+    }
+
+    #[test]
+    fn parses_old_x86_layout() {
+        let desc = IrqDesc::parse("IO-APIC-edge      timer");
+        assert_eq!(desc.chip, "IO-APIC");
+        assert_eq!(desc.hwirq, None);
+        assert_eq!(desc.trigger, "edge");
+        assert_eq!(desc.actions, ["timer"]);
+    }
+
+    #[test]
+    fn splits_shared_actions() {
+        let desc = IrqDesc::parse("IO-APIC   16-fasteoi   ehci_hcd:usb1, snd_hda_intel");
+        assert_eq!(desc.trigger, "fasteoi");
+        assert_eq!(desc.actions, ["ehci_hcd:usb1", "snd_hda_intel"]);
+    }
+
+    #[test]
+    fn keeps_unknown_layout_as_chip_and_actions() {
+        let desc = IrqDesc::parse("dummy  something");
+        assert_eq!(desc.chip, "dummy");
+        assert_eq!(desc.hwirq, None);
+        assert_eq!(desc.trigger, "");
+        assert_eq!(desc.actions, ["something"]);
+    }
+}
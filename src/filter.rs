@@ -0,0 +1,428 @@
+use crate::interrupts::{IrqStats, Snapshot};
+use crate::irq_id::IrqId;
+use anyhow::{bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Case-insensitive text pattern matched against an IRQ's id, device and chip:
+/// a literal substring, an unanchored regex, or an anchored glob
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    text: String,
+    regex: Option<Regex>,
+}
+
+impl Pattern {
+    /// Match `text` as a substring, metacharacters included
+    pub fn literal(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            regex: None,
+        }
+    }
+
+    /// Match the regex `text` anywhere in the string
+    pub fn regex(text: &str) -> Result<Self> {
+        let regex = RegexBuilder::new(text)
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("invalid regex {:?}", text))?;
+        Ok(Self {
+            text: text.to_string(),
+            regex: Some(regex),
+        })
+    }
+
+    /// Shell-style glob matched against the whole string: `*` and `?` are
+    /// wildcards and `|` separates alternatives, e.g. `nvme*|mlx5*`
+    pub fn glob(text: &str) -> Result<Self> {
+        let alternatives: Vec<String> = text
+            .split('|')
+            .map(|alt| {
+                alt.chars()
+                    .map(|c| match c {
+                        '*' => ".*".to_string(),
+                        '?' => ".".to_string(),
+                        c => regex::escape(c.encode_utf8(&mut [0; 4])),
+                    })
+                    .collect()
+            })
+            .collect();
+        let regex = RegexBuilder::new(&format!("^(?:{})$", alternatives.join("|")))
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("invalid pattern {:?}", text))?;
+        Ok(Self {
+            text: text.to_string(),
+            regex: Some(regex),
+        })
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(haystack),
+            None => haystack.to_lowercase().contains(&self.text.to_lowercase()),
+        }
+    }
+
+    /// Whether the IRQ id, device or chip matches
+    pub fn matches(&self, irq: &IrqId, stats: &IrqStats) -> bool {
+        self.is_match(&irq.to_string()) || self.matches_device(stats) || self.is_match(&stats.desc.chip)
+    }
+
+    /// Whether any of the handler actions sharing the IRQ matches, so a glob
+    /// like `snd*` finds `ehci_hcd:usb1, snd_hda_intel`; rows without
+    /// actions match on their description
+    pub fn matches_device(&self, stats: &IrqStats) -> bool {
+        if stats.desc.actions.is_empty() {
+            self.is_match(&stats.device())
+        } else {
+            stats.desc.actions.iter().any(|action| self.is_match(action))
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// IRQ list such as `24,30-40,LOC`: numbers, inclusive ranges and named rows
+#[derive(Debug, Clone)]
+pub struct IrqSet {
+    text: String,
+    ranges: Vec<RangeInclusive<u32>>,
+    names: Vec<String>,
+}
+
+impl IrqSet {
+    pub fn contains(&self, irq: &IrqId) -> bool {
+        match irq {
+            IrqId::Numeric(n) => self.ranges.iter().any(|r| r.contains(n)),
+            IrqId::Named(name) => self.names.iter().any(|n| n == name),
+        }
+    }
+}
+
+impl FromStr for IrqSet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut ranges = Vec::new();
+        let mut names = Vec::new();
+        for part in s.split(',').map(str::trim) {
+            match parse_range(part) {
+                Some(range) => ranges.push(range?),
+                None if !part.is_empty() && !part.contains('-') => names.push(part.to_string()),
+                None => bail!("invalid IRQ {:?} in {:?}", part, s),
+            }
+        }
+        Ok(Self {
+            text: s.to_string(),
+            ranges,
+            names,
+        })
+    }
+}
+
+impl fmt::Display for IrqSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// CPU list in the kernel's `cpulist` syntax, e.g. `0-7,16`
+#[derive(Debug, Clone)]
+pub struct CpuList {
+    text: String,
+    ranges: Vec<RangeInclusive<u32>>,
+}
+
+impl CpuList {
+    pub fn contains(&self, cpu: u32) -> bool {
+        self.ranges.iter().any(|r| r.contains(&cpu))
+    }
+}
+
+impl FromStr for CpuList {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let ranges = s
+            .split(',')
+            .map(|part| parse_range(part.trim()).with_context(|| format!("invalid CPU {:?} in {:?}", part, s))?)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            text: s.to_string(),
+            ranges,
+        })
+    }
+}
+
+impl fmt::Display for CpuList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// `n` or `a-b`; `None` when `part` is neither
+fn parse_range(part: &str) -> Option<Result<RangeInclusive<u32>>> {
+    let (start, end) = part.split_once('-').unwrap_or((part, part));
+    let (Ok(start), Ok(end)) = (start.trim().parse::<u32>(), end.trim().parse::<u32>()) else {
+        return None;
+    };
+    if start > end {
+        return Some(Err(anyhow::anyhow!("empty range {:?}", part)));
+    }
+    Some(Ok(start..=end))
+}
+
+/// Row filter expression shared by `--filter` and the TUI's `/` prompt.
+///
+/// Whitespace-separated terms that a row has to match all of, built from
+/// the same parts as the individual options:
+///
+/// - `irq:24,30-40` - IRQ list, like `--irq`
+/// - `dev:nvme*|mlx5*` - device glob, like `--device`
+/// - `!mlx5*` - id, device or chip glob to drop, like `--exclude`
+/// - `re:^nvme\d+q` - regex against the id, device or chip
+/// - anything else - case-insensitive substring of the id, device or chip
+#[derive(Debug, Clone)]
+pub struct Search {
+    text: String,
+    terms: Vec<SearchTerm>,
+}
+
+#[derive(Debug, Clone)]
+enum SearchTerm {
+    Irqs(IrqSet),
+    Device(Pattern),
+    Exclude(Pattern),
+    /// Regex or literal text against id, device and chip
+    Text(Pattern),
+}
+
+impl Search {
+    pub fn matches(&self, irq: &IrqId, stats: &IrqStats) -> bool {
+        self.terms.iter().all(|term| match term {
+            SearchTerm::Irqs(set) => set.contains(irq),
+            SearchTerm::Device(p) => p.matches_device(stats),
+            SearchTerm::Exclude(p) => !p.matches(irq, stats),
+            SearchTerm::Text(p) => p.matches(irq, stats),
+        })
+    }
+}
+
+impl FromStr for Search {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let terms = s
+            .split_whitespace()
+            .map(|term| {
+                let (prefix, value) = match term.split_once(':') {
+                    Some((prefix @ ("irq" | "dev" | "re"), value)) => (prefix, value),
+                    _ => match term.strip_prefix('!') {
+                        Some(value) => ("!", value),
+                        None => return Ok(SearchTerm::Text(Pattern::literal(term))),
+                    },
+                };
+                if value.is_empty() {
+                    bail!("empty {:?} term", term);
+                }
+                Ok(match prefix {
+                    "irq" => SearchTerm::Irqs(value.parse()?),
+                    "dev" => SearchTerm::Device(Pattern::glob(value)?),
+                    "re" => SearchTerm::Text(Pattern::regex(value)?),
+                    _ => SearchTerm::Exclude(Pattern::glob(value)?),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            text: s.trim().to_string(),
+            terms,
+        })
+    }
+}
+
+impl fmt::Display for Search {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Selection of rows and CPUs shared by the TUI, batch output and exporters.
+///
+/// The command-line options fill every field and the TUI's `/` prompt
+/// edits `search`; a row has to pass all of them.
+#[derive(Debug, Clone, Default)]
+pub struct IrqFilter {
+    /// Only these IRQs (`--irq`)
+    pub irqs: Option<IrqSet>,
+    /// Only IRQs with a handler action that matches (`--device`)
+    pub device: Option<Pattern>,
+    /// Drop IRQs whose id, device or chip matches (`--exclude`)
+    pub exclude: Option<Pattern>,
+    /// Only count and show these CPUs (`--cpu`)
+    pub cpus: Option<CpuList>,
+    /// Drop IRQs without activity on the selected CPUs (`--hide-idle`)
+    pub hide_idle: bool,
+    /// Filter expression (`--filter`, `/` in the TUI)
+    pub search: Option<Search>,
+}
+
+impl IrqFilter {
+    pub fn is_empty(&self) -> bool {
+        self.irqs.is_none()
+            && self.device.is_none()
+            && self.exclude.is_none()
+            && self.cpus.is_none()
+            && !self.hide_idle
+            && self.search.is_none()
+    }
+
+    /// Whether a row passes the IRQ list, the device and exclude patterns
+    /// and the search expression
+    pub fn matches(&self, irq: &IrqId, stats: &IrqStats) -> bool {
+        self.irqs.as_ref().is_none_or(|set| set.contains(irq))
+            && self.device.as_ref().is_none_or(|p| p.matches_device(stats))
+            && self.exclude.as_ref().is_none_or(|p| !p.matches(irq, stats))
+            && self.search.as_ref().is_none_or(|s| s.matches(irq, stats))
+    }
+
+    /// Like [`matches`](IrqFilter::matches), additionally dropping rows whose
+    /// `activity` (a delta or counter summed with
+    /// [`sum_selected`](IrqFilter::sum_selected)) is zero under `--hide-idle`
+    pub fn keeps(&self, irq: &IrqId, stats: &IrqStats, activity: u64) -> bool {
+        (!self.hide_idle || activity > 0) && self.matches(irq, stats)
+    }
+
+    pub fn has_cpu(&self, cpu: u32) -> bool {
+        self.cpus.as_ref().is_none_or(|c| c.contains(cpu))
+    }
+
+    /// Whether counter column `idx` of a per-CPU row belongs to a selected CPU
+    pub fn keeps_column(&self, snapshot: &Snapshot, idx: usize) -> bool {
+        self.has_cpu(snapshot.cpu_id(idx))
+    }
+
+    /// Sum of a row's per-CPU `values` (counters or deltas) over the selected
+    /// CPUs; global error rows have no CPUs and are always summed in full
+    pub fn sum_selected(&self, snapshot: &Snapshot, stats: &IrqStats, values: &[u64]) -> u64 {
+        if self.cpus.is_none() || stats.is_global() {
+            return values.iter().sum();
+        }
+        values.iter()
+            .enumerate()
+            .filter(|(idx, _)| self.keeps_column(snapshot, *idx))
+            .map(|(_, v)| v)
+            .sum()
+    }
+}
+
+impl fmt::Display for IrqFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(irqs) = &self.irqs {
+            parts.push(format!("irq={}", irqs));
+        }
+        if let Some(device) = &self.device {
+            parts.push(format!("device={}", device));
+        }
+        if let Some(exclude) = &self.exclude {
+            parts.push(format!("exclude={}", exclude));
+        }
+        if let Some(cpus) = &self.cpus {
+            parts.push(format!("cpu={}", cpus));
+        }
+        if self.hide_idle {
+            parts.push("hide-idle".to_string());
+        }
+        if let Some(search) = &self.search {
+            parts.push(format!("\"{}\"", search));
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interrupts::parse_interrupts;
+
+    const INTERRUPTS: &[u8] = b"           CPU0       CPU1
+  16:         10          0  IR-PCI-MSI 32768-edge      ahci[0000:00:1f.2]
+  17:          4          0  IO-APIC   17-fasteoi   ehci_hcd:usb1, snd_hda_intel
+  24:          5          7  IR-PCI-MSI 524288-edge      nvme0q1
+  31:          0          3  IR-PCI-MSI 1048576-edge      mlx5_comp0@pci:0000:3b:00.0
+  33:          1          1  IR-PCI-MSI 1572864-edge      mlx4_core
+ LOC:        100        200   Local timer interrupts
+";
+
+    /// Ids of the rows `search` keeps
+    fn search(search: &str) -> Vec<String> {
+        let search: Search = search.parse().unwrap();
+        parse_interrupts(INTERRUPTS)
+            .iter()
+            .filter(|(irq, stats)| search.matches(irq, stats))
+            .map(|(irq, _)| irq.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn search_text_is_literal() {
+        assert_eq!(search("ahci[0000:00:1f.2]"), ["16"]);
+        assert_eq!(search("NVME0"), ["24"]);
+        assert_eq!(search("mlx.*"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn search_regex_needs_prefix() {
+        assert_eq!(search("re:^mlx\\d"), ["31", "33"]);
+        assert_eq!(search("re:^l.c$"), ["LOC"]);
+        assert!("re:(".parse::<Search>().is_err());
+        assert!("re:".parse::<Search>().is_err());
+    }
+
+    #[test]
+    fn search_terms_match_like_the_options() {
+        assert_eq!(search("irq:24,30-40"), ["24", "31", "33"]);
+        assert_eq!(search("irq:LOC"), ["LOC"]);
+        assert_eq!(search("dev:nvme*|mlx5*"), ["24", "31"]);
+        assert_eq!(search("!mlx*"), ["16", "17", "24", "LOC"]);
+        assert_eq!(search("irq:16-40 !nvme* dev:mlx*"), ["31", "33"]);
+        assert!("irq:x-1".parse::<Search>().is_err());
+        assert!("dev:".parse::<Search>().is_err());
+        assert!("!".parse::<Search>().is_err());
+    }
+
+    #[test]
+    fn device_globs_match_each_shared_action() {
+        assert_eq!(search("dev:snd*"), ["17"]);
+        assert_eq!(search("dev:ehci_hcd:usb1"), ["17"]);
+        assert_eq!(search("dev:ehci*snd*"), Vec::<String>::new());
+        assert_eq!(search("dev:local*"), ["LOC"]);
+        assert_eq!(search("!snd*"), ["16", "24", "31", "33", "LOC"]);
+
+        let filter = IrqFilter {
+            device: Some(Pattern::glob("snd*").unwrap()),
+            ..IrqFilter::default()
+        };
+        let snapshot = parse_interrupts(INTERRUPTS);
+        let kept: Vec<String> = snapshot
+            .iter()
+            .filter(|(irq, stats)| filter.matches(irq, stats))
+            .map(|(irq, _)| irq.to_string())
+            .collect();
+        assert_eq!(kept, ["17"]);
+    }
+
+    #[test]
+    fn search_terms_must_all_match() {
+        assert_eq!(search("mlx re:core$"), ["33"]);
+        assert_eq!(search(""), ["16", "17", "24", "31", "33", "LOC"]);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::delta::per_second;
+use crate::filter::IrqFilter;
+use crate::irq_id::IrqId;
+use crate::sampler::Sampler;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Bounded history of recent rates, per IRQ and per CPU.
+///
+/// Each [`push`](RateHistory::push) appends one slot per series and drops
+/// the oldest once `depth` slots are kept, so a series never grows past
+/// `depth` and steady-state pushes do not allocate. Series are aligned on
+/// their newest slot: an IRQ that appeared later simply has a shorter one.
+#[derive(Debug)]
+pub struct RateHistory {
+    depth: usize,
+    started: Option<Instant>,
+    /// Time of the newest pushed sample
+    last: Option<Instant>,
+    /// Seconds since the first pushed sample, one per slot
+    times: VecDeque<f64>,
+    irqs: HashMap<IrqId, VecDeque<f64>>,
+    /// Total rate of all per-CPU rows, by CPU id so a series stays with its
+    /// CPU when others go offline
+    cpus: HashMap<u32, VecDeque<f64>>,
+    /// Scratch sums by count column
+    cpu_totals: Vec<u64>,
+}
+
+impl RateHistory {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth: depth.max(1),
+            started: None,
+            last: None,
+            times: VecDeque::new(),
+            irqs: HashMap::new(),
+            cpus: HashMap::new(),
+            cpu_totals: Vec::new(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Forget every series, e.g. after jumping within a replay
+    pub fn clear(&mut self) {
+        self.started = None;
+        self.last = None;
+        self.times.clear();
+        self.irqs.clear();
+        self.cpus.clear();
+    }
+
+    /// Append the sampler's current rates.
+    ///
+    /// IRQ rates cover the CPUs `filter` selects, matching what the table
+    /// shows; the per-CPU series sum every per-CPU row regardless of filter.
+    /// IRQs and CPUs that are no longer listed are dropped. Pushing the newest sample
+    /// again does nothing, and an older one (a replay stepping back) starts
+    /// the history over.
+    pub fn push(&mut self, sampler: &Sampler, filter: &IrqFilter) {
+        let data = sampler.current();
+        let elapsed = sampler.elapsed();
+        // The first sample has nothing to compare against
+        if sampler.deltas().is_empty() || self.last == Some(data.taken_at) {
+            return;
+        }
+        if self.last.is_some_and(|last| data.taken_at < last) {
+            self.clear();
+        }
+        self.last = Some(data.taken_at);
+        let started = *self.started.get_or_insert(data.taken_at);
+        push_bounded(&mut self.times, data.taken_at.saturating_duration_since(started).as_secs_f64(), self.depth);
+
+        self.cpu_totals.clear();
+        self.cpu_totals.resize(data.cpus.len(), 0);
+        for delta in sampler.deltas() {
+            let Some(stats) = data.get(&delta.irq) else {
+                continue;
+            };
+            let rate = per_second(filter.sum_selected(data, stats, &delta.per_cpu), elapsed);
+            match self.irqs.get_mut(&delta.irq) {
+                Some(series) => push_bounded(series, rate, self.depth),
+                None => {
+                    let mut series = VecDeque::with_capacity(self.depth);
+                    series.push_back(rate);
+                    self.irqs.insert(delta.irq.clone(), series);
+                }
+            }
+            if !stats.is_global() {
+                for (total, d) in self.cpu_totals.iter_mut().zip(&delta.per_cpu) {
+                    *total += d;
+                }
+            }
+        }
+        self.irqs.retain(|irq, _| data.get(irq).is_some());
+
+        for (idx, total) in self.cpu_totals.iter().enumerate() {
+            let rate = per_second(*total, elapsed);
+            match self.cpus.get_mut(&data.cpu_id(idx)) {
+                Some(series) => push_bounded(series, rate, self.depth),
+                None => {
+                    let mut series = VecDeque::with_capacity(self.depth);
+                    series.push_back(rate);
+                    self.cpus.insert(data.cpu_id(idx), series);
+                }
+            }
+        }
+        let columns = self.cpu_totals.len();
+        self.cpus.retain(|cpu, _| (0..columns).any(|idx| data.cpu_id(idx) == *cpu));
+    }
+
+    /// Time of each slot in seconds since the first sample, oldest first
+    pub fn times(&self) -> &VecDeque<f64> {
+        &self.times
+    }
+
+    /// Rates of one IRQ, oldest first
+    pub fn irq(&self, irq: &IrqId) -> Option<&VecDeque<f64>> {
+        self.irqs.get(irq)
+    }
+
+    /// Total rates of CPU `cpu`, oldest first
+    pub fn cpu(&self, cpu: u32) -> Option<&VecDeque<f64>> {
+        self.cpus.get(&cpu)
+    }
+}
+
+fn push_bounded(series: &mut VecDeque<f64>, value: f64, depth: usize) {
+    if series.len() == depth {
+        series.pop_front();
+    }
+    series.push_back(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interrupts::parse_interrupts_into;
+    use crate::roots::Roots;
+    use std::time::Duration;
+
+    const TWO_CPUS: &str = "           CPU0       CPU1
+  24:        {a}        {b}   IR-PCI-MSI 524288-edge      nvme0q1
+ LOC:        {a}        {a}   Local timer interrupts
+";
+
+    /// Feed `text` to the sampler as read `secs` after `origin`
+    fn sample(sampler: &mut Sampler, origin: Instant, secs: u64, text: &str) {
+        sampler
+            .sample_with(|snapshot| {
+                parse_interrupts_into(text.as_bytes(), snapshot);
+                snapshot.taken_at = origin + Duration::from_secs(secs);
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    fn counts(a: u64, b: u64) -> String {
+        TWO_CPUS.replace("{a}", &a.to_string()).replace("{b}", &b.to_string())
+    }
+
+    /// History of depth `depth` after samples at 0, 1, 2, ... seconds where
+    /// the counters reach `a` on CPU0 and `b` on CPU1
+    fn history(depth: usize, samples: &[(u64, u64)]) -> (RateHistory, Sampler, Instant) {
+        let origin = Instant::now();
+        let mut sampler = Sampler::new(Roots::default());
+        let mut history = RateHistory::new(depth);
+        for (secs, &(a, b)) in samples.iter().enumerate() {
+            sample(&mut sampler, origin, secs as u64, &counts(a, b));
+            history.push(&sampler, &IrqFilter::default());
+        }
+        (history, sampler, origin)
+    }
+
+    #[test]
+    fn keeps_at_most_depth_slots() {
+        let (history, _, _) = history(3, &[(0, 0), (1, 0), (3, 0), (6, 0), (10, 1)]);
+        assert_eq!(history.times(), &[1.0, 2.0, 3.0]);
+        assert_eq!(history.irq(&IrqId::Numeric(24)).unwrap(), &[2.0, 3.0, 5.0]);
+        assert_eq!(history.cpu(0).unwrap(), &[4.0, 6.0, 8.0]);
+        assert_eq!(history.cpu(1).unwrap(), &[2.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn ignores_the_first_and_repeated_samples() {
+        let (history, _, _) = history(10, &[(0, 0)]);
+        assert!(history.times().is_empty());
+
+        let (mut history, sampler, _) = self::history(10, &[(0, 0), (1, 0)]);
+        history.push(&sampler, &IrqFilter::default());
+        assert_eq!(history.times().len(), 1);
+        assert_eq!(history.irq(&IrqId::Numeric(24)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn starts_over_when_time_goes_back() {
+        let (mut history, mut sampler, origin) = history(10, &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(history.times().len(), 3);
+
+        // A replay stepping back feeds an older pair of frames
+        sample(&mut sampler, origin, 0, &counts(0, 0));
+        sample(&mut sampler, origin, 1, &counts(5, 0));
+        history.push(&sampler, &IrqFilter::default());
+        assert_eq!(history.times(), &[0.0]);
+        assert_eq!(history.irq(&IrqId::Numeric(24)).unwrap(), &[5.0]);
+    }
+
+    #[test]
+    fn drops_irqs_and_cpus_that_disappear() {
+        let (mut history, mut sampler, origin) = history(10, &[(0, 0), (1, 1), (2, 2)]);
+        // IRQ 24 goes away, CPU1 goes offline and IRQ 25 shows up
+        let three = "           CPU0       CPU2
+  25:          0          0   IR-PCI-MSI 524289-edge      nvme0q2
+ LOC:          2          2   Local timer interrupts
+";
+        let four = three.replace("  25:          0          0", "  25:          1          1");
+        sample(&mut sampler, origin, 3, three);
+        history.push(&sampler, &IrqFilter::default());
+        sample(&mut sampler, origin, 4, &four);
+        history.push(&sampler, &IrqFilter::default());
+
+        assert!(history.irq(&IrqId::Numeric(24)).is_none());
+        assert_eq!(history.irq(&IrqId::Numeric(25)).unwrap(), &[2.0]);
+        assert_eq!(history.irq(&IrqId::parse("LOC")).unwrap().len(), 4);
+        assert!(history.cpu(1).is_none());
+        // CPU2 starts its own series rather than continuing CPU1's column
+        assert_eq!(history.cpu(0).unwrap().len(), 4);
+        assert_eq!(history.cpu(2).unwrap(), &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn irq_rates_cover_the_filtered_cpus() {
+        let origin = Instant::now();
+        let mut sampler = Sampler::new(Roots::default());
+        let mut history = RateHistory::new(10);
+        let filter = IrqFilter {
+            cpus: Some("1".parse().unwrap()),
+            ..IrqFilter::default()
+        };
+        for (secs, (a, b)) in [(0, 0), (4, 2)].into_iter().enumerate() {
+            sample(&mut sampler, origin, secs as u64, &counts(a, b));
+            history.push(&sampler, &filter);
+        }
+        assert_eq!(history.irq(&IrqId::Numeric(24)).unwrap(), &[2.0]);
+        // The per-CPU series ignore the filter
+        assert_eq!(history.cpu(0).unwrap(), &[8.0]);
+    }
+}
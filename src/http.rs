@@ -0,0 +1,141 @@
+use anyhow::{bail, Context, Result};
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Minimal blocking `POST` for plain `http://` URLs
+pub fn post(url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<()> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        bail!("unsupported URL {} (only http:// is supported)", url);
+    };
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let addr = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+
+    let mut stream = TcpStream::connect(&addr).with_context(|| format!("failed to connect to {}", addr))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        path,
+        host,
+        body.len()
+    )?;
+    for (name, value) in headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    stream.write_all(b"\r\n")?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut response = BufReader::new(stream);
+    let mut status_line = String::new();
+    response.read_line(&mut status_line)?;
+    let status = status_line.split_whitespace().nth(1).unwrap_or("");
+    if !status.starts_with('2') {
+        let mut text = String::new();
+        let _ = response.take(4096).read_to_string(&mut text);
+        let body = text.split("\r\n\r\n").nth(1).unwrap_or("").trim();
+        bail!("POST {} failed: {} {}", url, status_line.trim(), body);
+    }
+    Ok(())
+}
+
+/// One-shot local server standing in for a real endpoint in tests
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    /// A request as received by [`serve_once`]
+    pub struct Request {
+        pub request_line: String,
+        pub headers: Vec<String>,
+        pub body: Vec<u8>,
+    }
+
+    /// Accept one request on 127.0.0.1 and answer it with `status` and
+    /// `reply`; returns the server's base URL and the received request
+    pub fn serve_once(status: &'static str, reply: &'static str) -> (String, JoinHandle<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut headers = Vec::new();
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(value) = line.strip_prefix("Content-Length: ") {
+                    len = value.parse().unwrap();
+                }
+                headers.push(line.to_string());
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reply.len(),
+                reply
+            )
+            .unwrap();
+            Request {
+                request_line: request_line.trim_end().to_string(),
+                headers,
+                body,
+            }
+        });
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::serve_once;
+    use super::*;
+
+    #[test]
+    fn sends_request_line_headers_and_body() {
+        let (url, server) = serve_once("204 No Content", "");
+        post(&format!("{}/api/v2/write?bucket=irq", url), &[("Authorization", "Token secret")], b"m v=1i 1\n")
+            .unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(request.request_line, "POST /api/v2/write?bucket=irq HTTP/1.1");
+        let host = url.trim_start_matches("http://");
+        assert!(request.headers.contains(&format!("Host: {}", host)));
+        assert!(request.headers.contains(&"Content-Length: 9".to_string()));
+        assert!(request.headers.contains(&"Authorization: Token secret".to_string()));
+        assert_eq!(request.body, b"m v=1i 1\n");
+    }
+
+    #[test]
+    fn reports_non_2xx_status_with_body() {
+        let (url, server) = serve_once("401 Unauthorized", "{\"code\":\"unauthorized\"}");
+        let err = post(&url, &[], b"x").unwrap_err().to_string();
+        server.join().unwrap();
+
+        assert!(err.contains("401 Unauthorized"), "{}", err);
+        assert!(err.contains("unauthorized\"}"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_other_schemes() {
+        assert!(post("https://example.com/", &[], b"").is_err());
+    }
+}
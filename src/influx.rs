@@ -0,0 +1,55 @@
+//! InfluxDB line protocol.
+
+use crate::interrupts::IrqCategory;
+use crate::record::SampleRecord;
+use std::fmt;
+use std::io::{self, Write};
+use std::time::UNIX_EPOCH;
+
+/// Write one `interrupts` point per IRQ and CPU.
+///
+/// Tags are `irq`, `device`, `cpu` and `chip` (empty tags are omitted, and
+/// global error counters have no `cpu`); fields are the cumulative `count`
+/// and the per-second `rate`; timestamps are in nanoseconds.
+pub fn write_lines(out: &mut impl Write, record: &SampleRecord) -> io::Result<()> {
+    let ts = record.wall_clock.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let per_sec = if record.interval > 0.0 { 1.0 / record.interval } else { 0.0 };
+
+    for irq in &record.irqs {
+        let irq_name = irq.irq.to_string();
+        if irq.category == IrqCategory::Error {
+            write!(out, "interrupts,irq={}", Tag(&irq_name))?;
+            writeln!(out, " count={}i,rate={} {}", irq.total, irq.rate, ts)?;
+            continue;
+        }
+        for (idx, (count, delta)) in irq.counts.iter().zip(&irq.per_cpu).enumerate() {
+            write!(out, "interrupts,irq={}", Tag(&irq_name))?;
+            if !irq.device.is_empty() {
+                write!(out, ",device={}", Tag(&irq.device))?;
+            }
+            let cpu = record.cpus.get(idx).copied().unwrap_or(idx as u32);
+            write!(out, ",cpu={}", cpu)?;
+            if !irq.chip.is_empty() {
+                write!(out, ",chip={}", Tag(&irq.chip))?;
+            }
+            writeln!(out, " count={}i,rate={} {}", count, *delta as f64 * per_sec, ts)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tag value with commas, equals signs and spaces escaped
+struct Tag<'a>(&'a str);
+
+impl fmt::Display for Tag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                ',' | '=' | ' ' => write!(f, "\\{}", c)?,
+                '\n' => f.write_str("\\n")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,473 @@
+use crate::desc::IrqDesc;
+use crate::irq_id::IrqId;
+use crate::roots::Roots;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::os::unix::fs::FileExt;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Which family a `/proc/interrupts` row belongs to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IrqCategory {
+    /// Numbered device interrupt (`24:`, `131:`)
+    #[default]
+    Device,
+    /// Architecture-specific per-CPU row (`NMI`, `LOC`, `RES`, `CAL`, `TLB`, ...)
+    System,
+    /// Global counter without per-CPU columns or description (`ERR`, `MIS`)
+    Error,
+}
+
+impl IrqCategory {
+    pub const ALL: [IrqCategory; 3] = [IrqCategory::Device, IrqCategory::System, IrqCategory::Error];
+
+    /// Classify a row by its identifier and the text after its counters
+    pub fn classify(irq: &IrqId, name: &str) -> Self {
+        if irq.is_numeric() {
+            IrqCategory::Device
+        } else if name.is_empty() {
+            IrqCategory::Error
+        } else {
+            IrqCategory::System
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IrqCategory::Device => "Device",
+            IrqCategory::System => "System",
+            IrqCategory::Error => "Error",
+        }
+    }
+}
+
+/// Interrupt statistics
+#[derive(Debug, Default, Clone)]
+pub struct IrqStats {
+    /// Per-CPU counters, in `/proc/interrupts` column order; a single
+    /// system-wide value for [`IrqCategory::Error`] rows
+    pub counts: Vec<u64>,
+    /// Everything after the counters: chip, hwirq, trigger and actions
+    pub name: String,
+    /// `name` split into its parts; empty for named (non-numeric) rows
+    pub desc: IrqDesc,
+    pub category: IrqCategory,
+}
+
+impl IrqStats {
+    /// Sum of the per-CPU counters
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Comma-separated handler actions, or the raw description for rows
+    /// without a chip (e.g. `Local timer interrupts`)
+    pub fn device(&self) -> String {
+        match self.category {
+            IrqCategory::Device => self.desc.actions.join(", "),
+            IrqCategory::System => self.name.clone(),
+            IrqCategory::Error => "Global error counter".to_string(),
+        }
+    }
+
+    /// Whether `counts` holds one system-wide value rather than per-CPU columns
+    pub fn is_global(&self) -> bool {
+        self.category == IrqCategory::Error
+    }
+}
+
+/// One parsed read of `/proc/interrupts`, keyed by IRQ identifier
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Monotonic time the counters were read
+    pub taken_at: Instant,
+    /// Wall-clock time the counters were read
+    pub wall_clock: SystemTime,
+    /// CPU ids from the `CPU0 CPU1 ...` header; column `i` of every
+    /// [`IrqStats::counts`] belongs to CPU `cpus[i]`
+    pub cpus: Vec<u32>,
+    /// Rows by id; read through [`get`](Snapshot::get) and
+    /// [`iter`](Snapshot::iter) so they can't drift from `order`
+    irqs: HashMap<IrqId, IrqStats>,
+    /// Keys of `irqs` in file order
+    order: Vec<IrqId>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            taken_at: Instant::now(),
+            wall_clock: SystemTime::now(),
+            cpus: Vec::new(),
+            irqs: HashMap::new(),
+            order: Vec::new(),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Time between `earlier` and this snapshot
+    pub fn elapsed_since(&self, earlier: &Snapshot) -> Duration {
+        self.taken_at.saturating_duration_since(earlier.taken_at)
+    }
+
+    /// CPU id of count column `idx`, falling back to the column index when
+    /// the header was missing or shorter than the row
+    pub fn cpu_id(&self, idx: usize) -> u32 {
+        self.cpus.get(idx).copied().unwrap_or(idx as u32)
+    }
+
+    pub fn get(&self, irq: &IrqId) -> Option<&IrqStats> {
+        self.irqs.get(irq)
+    }
+
+    pub fn len(&self) -> usize {
+        self.irqs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.irqs.is_empty()
+    }
+
+    /// Rows in `/proc/interrupts` order
+    pub fn iter(&self) -> impl Iterator<Item = (&IrqId, &IrqStats)> {
+        self.order.iter().filter_map(|irq| self.irqs.get_key_value(irq))
+    }
+}
+
+/// Optimized /proc/interrupts reader
+pub fn read_interrupts(roots: &Roots) -> Result<Snapshot> {
+    // Read file as raw bytes to avoid UTF-8 validation
+    let path = roots.proc_path("interrupts");
+    let content = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse_interrupts(&content))
+}
+
+/// Persistent `/proc/interrupts` handle for sampling in a loop.
+///
+/// Keeps the file descriptor open and re-reads it with `pread` into a reused
+/// buffer, so once the buffer and the target [`Snapshot`] have grown to fit,
+/// [`read_into`](InterruptsReader::read_into) does not allocate.
+#[derive(Debug)]
+pub struct InterruptsReader {
+    file: File,
+    buf: Vec<u8>,
+    /// Bytes of `buf` filled by the last read
+    len: usize,
+}
+
+impl InterruptsReader {
+    pub fn open(roots: &Roots) -> Result<Self> {
+        let path = roots.proc_path("interrupts");
+        let file = File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self {
+            file,
+            buf: vec![0; 64 * 1024],
+            len: 0,
+        })
+    }
+
+    /// Re-read the file and update `snapshot` in place
+    pub fn read_into(&mut self, snapshot: &mut Snapshot) -> Result<()> {
+        self.read_raw()?;
+        parse_interrupts_into(&self.buf[..self.len], snapshot);
+        Ok(())
+    }
+
+    /// Re-read the file and return its unparsed contents
+    pub fn read_raw(&mut self) -> Result<&[u8]> {
+        let mut len = 0;
+        loop {
+            if len == self.buf.len() {
+                self.buf.resize(self.buf.len() * 2, 0);
+            }
+            let n = self.file.read_at(&mut self.buf[len..], len as u64)?;
+            if n == 0 {
+                break;
+            }
+            len += n;
+        }
+        self.len = len;
+        Ok(&self.buf[..len])
+    }
+}
+
+/// Parse the raw contents of `/proc/interrupts`, stamped with the current time
+pub fn parse_interrupts(content: &[u8]) -> Snapshot {
+    let mut snapshot = Snapshot::default();
+    parse_interrupts_into(content, &mut snapshot);
+    snapshot
+}
+
+/// Parse the raw contents of `/proc/interrupts` into an existing snapshot.
+///
+/// When the file lists the same rows as the snapshot's previous parse, which
+/// is the steady state, only the counters are overwritten and nothing is
+/// allocated. Otherwise the snapshot is rebuilt from scratch.
+pub fn parse_interrupts_into(content: &[u8], snapshot: &mut Snapshot) {
+    if !update_in_place(content, snapshot) {
+        rebuild(content, snapshot);
+    }
+    snapshot.taken_at = Instant::now();
+    snapshot.wall_clock = SystemTime::now();
+}
+
+/// Overwrite counters of a snapshot whose rows match `content` one-to-one.
+/// Returns false, leaving the snapshot partially updated, on any mismatch.
+fn update_in_place(content: &[u8], snapshot: &mut Snapshot) -> bool {
+    let (header, rows) = split_rows(content);
+    parse_cpu_header(header, &mut snapshot.cpus);
+
+    let mut row = 0;
+    for (irq, rest) in rows {
+        let Some(id) = snapshot.order.get(row) else {
+            return false;
+        };
+        if !id.matches(irq) {
+            return false;
+        }
+        let Some(stats) = snapshot.irqs.get_mut(id) else {
+            return false;
+        };
+        let name = parse_counts(rest, &mut stats.counts);
+        if stats.counts.is_empty() {
+            return false;
+        }
+        // Actions change when a driver binds or unbinds; counters don't
+        if stats.name.as_bytes() != name {
+            stats.name = String::from_utf8_lossy(name).into_owned();
+            stats.category = IrqCategory::classify(id, &stats.name);
+            stats.desc = match stats.category {
+                IrqCategory::Device => IrqDesc::parse(&stats.name),
+                _ => IrqDesc::default(),
+            };
+        }
+        row += 1;
+    }
+    row == snapshot.order.len()
+}
+
+/// Parse `content` into a cleared snapshot
+fn rebuild(content: &[u8], snapshot: &mut Snapshot) {
+    snapshot.irqs.clear();
+    snapshot.order.clear();
+
+    let (header, rows) = split_rows(content);
+    parse_cpu_header(header, &mut snapshot.cpus);
+
+    for (irq, rest) in rows {
+        // Pre-size counters for the CPU count from the header
+        let mut counts = Vec::with_capacity(snapshot.cpus.len().max(1));
+        let name = String::from_utf8_lossy(parse_counts(rest, &mut counts)).into_owned();
+
+        // ERR/MIS carry a single global value and no description
+        if !counts.is_empty() {
+            let irq = IrqId::parse(&String::from_utf8_lossy(irq));
+            let category = IrqCategory::classify(&irq, &name);
+            let desc = match category {
+                IrqCategory::Device => IrqDesc::parse(&name),
+                _ => IrqDesc::default(),
+            };
+            snapshot.order.push(irq.clone());
+            snapshot.irqs.insert(
+                irq,
+                IrqStats {
+                    counts,
+                    name,
+                    desc,
+                    category,
+                },
+            );
+        }
+    }
+}
+
+/// Split the file into its header line and `(irq, rest)` pairs, where `irq`
+/// is the trimmed text before the first `:` and `rest` everything after it
+fn split_rows(content: &[u8]) -> (&[u8], impl Iterator<Item = (&[u8], &[u8])>) {
+    // 1. Use memchr for fast line splitting, in-place without allocation
+    let mut pos = 0;
+    let mut lines = std::iter::from_fn(move || {
+        if pos >= content.len() {
+            return None;
+        }
+        let end = memchr::memchr(b'\n', &content[pos..])
+            .map(|p| pos + p)
+            .unwrap_or(content.len());
+        let line = &content[pos..end];
+        pos = end + 1;
+        Some(line)
+    });
+
+    // Header line lists the online CPUs, which need not be contiguous
+    let header = lines.next().unwrap_or_default();
+
+    // 2. Fast IRQ number parsing
+    let rows = lines.filter_map(|line| {
+        let irq_end = memchr::memchr(b':', line)?;
+        let irq = line[..irq_end].trim_ascii();
+        if irq.is_empty() {
+            return None;
+        }
+        Some((irq, &line[irq_end + 1..]))
+    });
+    (header, rows)
+}
+
+/// Parse the counters at the start of `rest` into `counts` and return the
+/// trimmed description after them
+fn parse_counts<'a>(rest: &'a [u8], counts: &mut Vec<u64>) -> &'a [u8] {
+    counts.clear();
+
+    // 3. Parse counts in a single pass over the bytes
+    let mut num_start = 0;
+    while num_start < rest.len() {
+        while num_start < rest.len() && rest[num_start].is_ascii_whitespace() {
+            num_start += 1;
+        }
+
+        let mut num_end = num_start;
+        while num_end < rest.len() && (rest[num_end] == b',' || rest[num_end].is_ascii_digit()) {
+            num_end += 1;
+        }
+
+        if num_start == num_end {
+            break;
+        }
+
+        // 4. Fast u64 parsing without string allocation
+        let mut value: u64 = 0;
+        for &c in &rest[num_start..num_end] {
+            if c != b',' {
+                value = value * 10 + (c - b'0') as u64;
+            }
+        }
+        counts.push(value);
+
+        num_start = num_end;
+    }
+
+    // 5. Extract device name
+    rest[num_start..].trim_ascii()
+}
+
+/// Parse `           CPU0       CPU1       CPU3` into `[0, 1, 3]`
+fn parse_cpu_header(line: &[u8], cpus: &mut Vec<u32>) {
+    cpus.clear();
+    cpus.extend(
+        line.split(|c| c.is_ascii_whitespace())
+            .filter_map(|tok| tok.strip_prefix(b"CPU"))
+            .filter_map(|id| std::str::from_utf8(id).ok()?.parse::<u32>().ok()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const X86: &[u8] = b"           CPU0       CPU1       CPU3
+  0:         44          0          0   IO-APIC   2-edge      timer
+ 24:       1000        200          3   IR-PCI-MSI 524288-edge      nvme0q1
+NMI:          1          2          3   Non-maskable interrupts
+LOC:     123456     654321       1000   Local timer interrupts
+ERR:          7
+MIS:          0
+";
+
+    #[test]
+    fn parses_sparse_cpu_header() {
+        let snapshot = parse_interrupts(X86);
+        assert_eq!(snapshot.cpus, [0, 1, 3]);
+        assert_eq!(snapshot.cpu_id(2), 3);
+        // Falls back to the column index past the header
+        assert_eq!(snapshot.cpu_id(5), 5);
+    }
+
+    #[test]
+    fn parses_rows_in_file_order() {
+        let snapshot = parse_interrupts(X86);
+        let ids: Vec<String> = snapshot.iter().map(|(irq, _)| irq.to_string()).collect();
+        assert_eq!(ids, ["0", "24", "NMI", "LOC", "ERR", "MIS"]);
+
+        let nvme = snapshot.get(&IrqId::Numeric(24)).unwrap();
+        assert_eq!(nvme.counts, [1000, 200, 3]);
+        assert_eq!(nvme.total(), 1203);
+        assert_eq!(nvme.desc.chip, "IR-PCI-MSI");
+        assert_eq!(nvme.device(), "nvme0q1");
+    }
+
+    #[test]
+    fn classifies_rows() {
+        let snapshot = parse_interrupts(X86);
+        let category = |irq: &str| snapshot.get(&IrqId::parse(irq)).unwrap().category;
+        assert_eq!(category("24"), IrqCategory::Device);
+        assert_eq!(category("NMI"), IrqCategory::System);
+        assert_eq!(category("LOC"), IrqCategory::System);
+        assert_eq!(category("ERR"), IrqCategory::Error);
+        assert_eq!(category("MIS"), IrqCategory::Error);
+
+        let err = snapshot.get(&IrqId::parse("ERR")).unwrap();
+        assert!(err.is_global());
+        assert_eq!(err.counts, [7]);
+        assert_eq!(err.desc, IrqDesc::default());
+    }
+
+    #[test]
+    fn parses_arm_rows() {
+        let snapshot = parse_interrupts(
+            b"           CPU0       CPU1
+ 11:      12345      67890     GICv3  27 Level     arch_timer
+IPI0:        10         20       Rescheduling interrupts
+Err:          0
+",
+        );
+        let timer = snapshot.get(&IrqId::Numeric(11)).unwrap();
+        assert_eq!(timer.desc.hwirq, Some(27));
+        assert_eq!(timer.desc.trigger, "level");
+        assert_eq!(snapshot.get(&IrqId::parse("IPI0")).unwrap().category, IrqCategory::System);
+        assert_eq!(snapshot.get(&IrqId::parse("Err")).unwrap().category, IrqCategory::Error);
+    }
+
+    #[test]
+    fn parses_thousands_separators() {
+        let snapshot = parse_interrupts(b"  CPU0\n  5:  1,234,567   IO-APIC-edge  rtc0\n");
+        let rtc = snapshot.get(&IrqId::Numeric(5)).unwrap();
+        assert_eq!(rtc.counts, [1234567]);
+        assert_eq!(rtc.desc.chip, "IO-APIC");
+        assert_eq!(rtc.desc.trigger, "edge");
+    }
+
+    #[test]
+    fn reparse_updates_counters_and_rebuilds_on_new_rows() {
+        let mut snapshot = parse_interrupts(X86);
+        let updated = String::from_utf8_lossy(X86).replace("1000        200", "1500        200");
+        parse_interrupts_into(updated.as_bytes(), &mut snapshot);
+        assert_eq!(snapshot.get(&IrqId::Numeric(24)).unwrap().counts, [1500, 200, 3]);
+
+        let added = format!("{}  25:   1   2   3   IR-PCI-MSI 524289-edge  nvme0q2\n", updated);
+        parse_interrupts_into(added.as_bytes(), &mut snapshot);
+        assert_eq!(snapshot.len(), 7);
+        assert_eq!(snapshot.get(&IrqId::Numeric(25)).unwrap().device(), "nvme0q2");
+    }
+
+    #[test]
+    fn reads_through_roots() {
+        let dir = std::env::temp_dir().join(format!("irqtop-rs-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("interrupts"), X86).unwrap();
+        let roots = Roots::new(&dir, "/nonexistent");
+
+        let snapshot = read_interrupts(&roots).unwrap();
+        let mut reader = InterruptsReader::open(&roots).unwrap();
+        let mut reused = Snapshot::default();
+        reader.read_into(&mut reused).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(snapshot.len(), 6);
+        assert_eq!(reused.cpus, snapshot.cpus);
+        assert_eq!(reused.get(&IrqId::Numeric(24)).unwrap().counts, [1000, 200, 3]);
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// Row identifier from `/proc/interrupts`: the text before the `:`.
+///
+/// Orders numeric IRQs numerically first, then named rows in the order the
+/// kernel prints them (x86 `arch_show_interrupts`, then ARM's `IPIn`/`Err`),
+/// with unknown names last in lexical order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IrqId {
+    Numeric(u32),
+    Named(String),
+}
+
+/// Named rows in kernel print order; `IPI` stands for every `IPI<n>` row
+const NAMED_ORDER: &[&str] = &[
+    "NMI", "LOC", "SPU", "PMI", "IWI", "RTR", "PLT", "RES", "CAL", "TLB", "TRM", "THR", "DFR",
+    "MCE", "MCP", "HYP", "HRE", "HVS", "ERR", "MIS", "PIN", "NPI", "PIW", "PMN", "IPI", "Err",
+];
+
+impl IrqId {
+    pub fn parse(s: &str) -> Self {
+        let s = s.trim();
+        match s.parse() {
+            Ok(n) => IrqId::Numeric(n),
+            Err(_) => IrqId::Named(s.to_string()),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, IrqId::Numeric(_))
+    }
+
+    pub fn as_numeric(&self) -> Option<u32> {
+        match self {
+            IrqId::Numeric(n) => Some(*n),
+            IrqId::Named(_) => None,
+        }
+    }
+
+    /// Whether this is the id of a row whose trimmed prefix is `raw`,
+    /// compared without allocating
+    pub(crate) fn matches(&self, raw: &[u8]) -> bool {
+        match self {
+            IrqId::Numeric(n) => {
+                !raw.is_empty()
+                    && raw.iter().all(u8::is_ascii_digit)
+                    && raw.iter().try_fold(0u32, |acc, &c| acc.checked_mul(10)?.checked_add((c - b'0') as u32))
+                        == Some(*n)
+            }
+            IrqId::Named(s) => s.as_bytes() == raw,
+        }
+    }
+
+    /// Position in [`NAMED_ORDER`], with `IPI<n>` rows ranked by `n`
+    fn named_rank(name: &str) -> (usize, Option<u32>) {
+        let ipi = name.strip_prefix("IPI").and_then(|n| n.parse::<u32>().ok());
+        let key = if ipi.is_some() { "IPI" } else { name };
+        (NAMED_ORDER.iter().position(|n| *n == key).unwrap_or(NAMED_ORDER.len()), ipi)
+    }
+}
+
+impl Ord for IrqId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (IrqId::Numeric(a), IrqId::Numeric(b)) => a.cmp(b),
+            (IrqId::Numeric(_), IrqId::Named(_)) => Ordering::Less,
+            (IrqId::Named(_), IrqId::Numeric(_)) => Ordering::Greater,
+            (IrqId::Named(a), IrqId::Named(b)) => IrqId::named_rank(a)
+                .cmp(&IrqId::named_rank(b))
+                .then_with(|| a.cmp(b)),
+        }
+    }
+}
+
+impl PartialOrd for IrqId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for IrqId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IrqId::Numeric(n) => write!(f, "{}", n),
+            IrqId::Named(s) => f.write_str(s),
+        }
+    }
+}
+
+impl FromStr for IrqId {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(IrqId::parse(s))
+    }
+}
+
+impl Serialize for IrqId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl From<u32> for IrqId {
+    fn from(n: u32) -> Self {
+        IrqId::Numeric(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_numeric_then_kernel_order() {
+        let mut ids: Vec<IrqId> = ["Err", "IPI10", "LOC", "IPI1", "10", "zzz", "2", "IPI0", "NMI", "3"]
+            .iter()
+            .map(|s| IrqId::parse(s))
+            .collect();
+        ids.sort();
+        let names: Vec<String> = ids.iter().map(IrqId::to_string).collect();
+        assert_eq!(names, ["2", "3", "10", "NMI", "LOC", "IPI0", "IPI1", "IPI10", "Err", "zzz"]);
+    }
+
+    #[test]
+    fn matches_raw_prefix() {
+        assert!(IrqId::Numeric(24).matches(b"24"));
+        assert!(!IrqId::Numeric(24).matches(b"240"));
+        assert!(!IrqId::Numeric(24).matches(b""));
+        assert!(IrqId::parse("LOC").matches(b"LOC"));
+        assert!(!IrqId::parse("LOC").matches(b"NMI"));
+    }
+}
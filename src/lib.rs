@@ -0,0 +1,73 @@
+//! Interrupt sampling for Linux.
+//!
+//! This crate holds the parsing and sampling logic behind the `irqtop-rs`
+//! binary so it can be embedded in other tools:
+//!
+//! - [`read_interrupts`] / [`parse_interrupts`] produce a [`Snapshot`] of
+//!   `/proc/interrupts` keyed by [`IrqId`], with each row's chip, hwirq, trigger and actions
+//!   split out into an [`IrqDesc`] and every row tagged with its
+//!   [`IrqCategory`] (device IRQ, arch-specific row or global error counter)
+//! - [`calculate_delta`] turns two snapshots into per-IRQ [`IrqDelta`]s,
+//!   with rates computed from the snapshots' monotonic timestamps
+//! - [`Sampler`] keeps the previous and current snapshot and their deltas
+//!   for tools that sample in a loop
+//! - [`RateHistory`] keeps a bounded window of recent rates per IRQ and per
+//!   CPU for trend displays
+//! - [`IrqFilter`] selects rows by [`IrqSet`], device or chip [`Pattern`]s
+//!   or a [`Search`] expression, and CPUs by [`CpuList`]
+//! - [`SampleRecord`] bundles one tick's deltas and affinity into a
+//!   serializable record for exporters
+//! - [`prometheus`] writes snapshots in Prometheus text exposition format,
+//!   [`influx`] writes sample records as InfluxDB line protocol
+//! - [`capture`] reads and writes recorded streams of raw
+//!   `/proc/interrupts` snapshots and affinity
+//! - [`CpuTopology`] reads each CPU's socket and core from sysfs and groups
+//!   CPU columns per core, per socket or in runs of N ([`CpuGrouping`])
+//! - [`get_affinity_map`] / [`get_effective_affinity_map`] read the CPU
+//!   affinity of every IRQ from `/proc/irq`
+//!
+//! All readers resolve their paths through [`Roots`], which defaults to the
+//! live `/proc` and `/sys` but can point at a captured copy of either tree.
+//!
+//! ```no_run
+//! use irqtop_rs::{calculate_delta, read_interrupts, Roots};
+//!
+//! let roots = Roots::default();
+//! let before = read_interrupts(&roots)?;
+//! std::thread::sleep(std::time::Duration::from_secs(1));
+//! let after = read_interrupts(&roots)?;
+//! for delta in calculate_delta(&before, &after) {
+//!     println!("{}: {}", delta.irq, delta.total);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+mod affinity;
+pub mod capture;
+mod delta;
+mod desc;
+mod filter;
+mod history;
+pub mod influx;
+mod interrupts;
+mod irq_id;
+pub mod prometheus;
+mod record;
+mod roots;
+mod sampler;
+mod topology;
+
+pub use affinity::{get_affinity_map, get_effective_affinity_map, AffinityMap};
+pub use delta::{calculate_delta, calculate_delta_into, per_second, IrqDelta};
+pub use desc::IrqDesc;
+pub use filter::{CpuList, IrqFilter, IrqSet, Pattern, Search};
+pub use history::RateHistory;
+pub use interrupts::{
+    parse_interrupts, parse_interrupts_into, read_interrupts, InterruptsReader, IrqCategory, IrqStats,
+    Snapshot,
+};
+pub use irq_id::IrqId;
+pub use record::{IrqRecord, SampleRecord};
+pub use roots::Roots;
+pub use sampler::Sampler;
+pub use topology::{CpuBucket, CpuGrouping, CpuPlace, CpuTopology};
@@ -0,0 +1,229 @@
+//! Prometheus text exposition format (version 0.0.4).
+
+use crate::affinity::AffinityMap;
+use crate::filter::IrqFilter;
+use crate::interrupts::{IrqCategory, IrqStats, Snapshot};
+use crate::irq_id::IrqId;
+use std::io::{self, Write};
+
+/// `Content-Type` of the exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Write the cumulative counters of `snapshot` as Prometheus counters.
+///
+/// Per-CPU rows become `irqtop_interrupts_total{irq,device,chip,cpu}`;
+/// global error rows (`ERR`, `MIS`) become `irqtop_interrupt_errors_total{irq}`.
+/// Only IRQs and CPUs selected by `filter` are written; `--hide-idle`
+/// drops IRQs whose counters are all zero.
+pub fn write_counters(out: &mut impl Write, snapshot: &Snapshot, filter: &IrqFilter) -> io::Result<()> {
+    writeln!(out, "# HELP irqtop_interrupts_total Interrupts handled since boot, per IRQ and CPU.")?;
+    writeln!(out, "# TYPE irqtop_interrupts_total counter")?;
+    for (irq, stats) in selected(snapshot, filter).filter(|(_, s)| !s.is_global()) {
+        let device = stats.device();
+        for (idx, count) in stats.counts.iter().enumerate() {
+            if !filter.keeps_column(snapshot, idx) {
+                continue;
+            }
+            writeln!(
+                out,
+                "irqtop_interrupts_total{{irq=\"{}\",device=\"{}\",chip=\"{}\",cpu=\"{}\"}} {}",
+                Escaped(&irq.to_string()),
+                Escaped(&device),
+                Escaped(&stats.desc.chip),
+                snapshot.cpu_id(idx),
+                count
+            )?;
+        }
+    }
+
+    writeln!(out, "# HELP irqtop_interrupt_errors_total System-wide interrupt error counters (ERR, MIS).")?;
+    writeln!(out, "# TYPE irqtop_interrupt_errors_total counter")?;
+    for (irq, stats) in selected(snapshot, filter).filter(|(_, s)| s.category == IrqCategory::Error) {
+        writeln!(
+            out,
+            "irqtop_interrupt_errors_total{{irq=\"{}\"}} {}",
+            Escaped(&irq.to_string()),
+            stats.total()
+        )?;
+    }
+    Ok(())
+}
+
+/// Write per-IRQ counters summed over CPUs as
+/// `irqtop_irq_interrupts_total{irq,device,chip}`, over the CPUs `filter` selects
+pub fn write_irq_totals(out: &mut impl Write, snapshot: &Snapshot, filter: &IrqFilter) -> io::Result<()> {
+    writeln!(out, "# HELP irqtop_irq_interrupts_total Interrupts handled since boot, per IRQ summed over CPUs.")?;
+    writeln!(out, "# TYPE irqtop_irq_interrupts_total counter")?;
+    for (irq, stats) in selected(snapshot, filter).filter(|(_, s)| !s.is_global()) {
+        writeln!(
+            out,
+            "irqtop_irq_interrupts_total{{irq=\"{}\",device=\"{}\",chip=\"{}\"}} {}",
+            Escaped(&irq.to_string()),
+            Escaped(&stats.device()),
+            Escaped(&stats.desc.chip),
+            filter.sum_selected(snapshot, stats, &stats.counts)
+        )?;
+    }
+    Ok(())
+}
+
+/// Write the configured and effective affinity of every IRQ in `snapshot`
+/// that has one as an info metric, `irqtop_irq_affinity_info`, valued 1
+pub fn write_affinity_info(
+    out: &mut impl Write,
+    snapshot: &Snapshot,
+    affinity: &AffinityMap,
+    effective: &AffinityMap,
+    filter: &IrqFilter,
+) -> io::Result<()> {
+    writeln!(out, "# HELP irqtop_irq_affinity_info Configured and effective CPU affinity per IRQ.")?;
+    writeln!(out, "# TYPE irqtop_irq_affinity_info gauge")?;
+    for (irq, stats) in selected(snapshot, filter) {
+        let (Some(aff), eff) = (affinity.get(irq), effective.get(irq)) else {
+            continue;
+        };
+        writeln!(
+            out,
+            "irqtop_irq_affinity_info{{irq=\"{}\",device=\"{}\",affinity=\"{}\",effective_affinity=\"{}\"}} 1",
+            Escaped(&irq.to_string()),
+            Escaped(&stats.device()),
+            Escaped(aff),
+            Escaped(eff.map(|s| s.as_str()).unwrap_or(""))
+        )?;
+    }
+    Ok(())
+}
+
+/// Rows of `snapshot` that `filter` keeps, judging activity by the counters
+fn selected<'a>(snapshot: &'a Snapshot, filter: &'a IrqFilter) -> impl Iterator<Item = (&'a IrqId, &'a IrqStats)> {
+    snapshot.iter().filter(|(irq, stats)| filter.keeps(irq, stats, filter.sum_selected(snapshot, stats, &stats.counts)))
+}
+
+/// Label value with `\`, `"` and newlines escaped
+pub(crate) struct Escaped<'a>(pub &'a str);
+
+impl std::fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interrupts::parse_interrupts;
+
+    const INTERRUPTS: &[u8] = br#"           CPU0       CPU1       CPU3
+  24:          5          7          0  IR-PCI-MSI 524288-edge      nvme0q1
+  25:          0          0          0  IR-PCI-MSI 524289-edge      my"dev\x
+ LOC:        100        200        300   Local timer interrupts
+ ERR:          2
+ MIS:          0
+"#;
+
+    fn render(write: impl Fn(&mut Vec<u8>, &Snapshot, &IrqFilter) -> io::Result<()>, filter: &IrqFilter) -> String {
+        let mut out = Vec::new();
+        write(&mut out, &parse_interrupts(INTERRUPTS), filter).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_counters() {
+        let out = render(write_counters, &IrqFilter::default());
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# HELP irqtop_interrupts_total Interrupts handled since boot, per IRQ and CPU.",
+                "# TYPE irqtop_interrupts_total counter",
+                r#"irqtop_interrupts_total{irq="24",device="nvme0q1",chip="IR-PCI-MSI",cpu="0"} 5"#,
+                r#"irqtop_interrupts_total{irq="24",device="nvme0q1",chip="IR-PCI-MSI",cpu="1"} 7"#,
+                r#"irqtop_interrupts_total{irq="24",device="nvme0q1",chip="IR-PCI-MSI",cpu="3"} 0"#,
+                r#"irqtop_interrupts_total{irq="25",device="my\"dev\\x",chip="IR-PCI-MSI",cpu="0"} 0"#,
+                r#"irqtop_interrupts_total{irq="25",device="my\"dev\\x",chip="IR-PCI-MSI",cpu="1"} 0"#,
+                r#"irqtop_interrupts_total{irq="25",device="my\"dev\\x",chip="IR-PCI-MSI",cpu="3"} 0"#,
+                r#"irqtop_interrupts_total{irq="LOC",device="Local timer interrupts",chip="",cpu="0"} 100"#,
+                r#"irqtop_interrupts_total{irq="LOC",device="Local timer interrupts",chip="",cpu="1"} 200"#,
+                r#"irqtop_interrupts_total{irq="LOC",device="Local timer interrupts",chip="",cpu="3"} 300"#,
+                "# HELP irqtop_interrupt_errors_total System-wide interrupt error counters (ERR, MIS).",
+                "# TYPE irqtop_interrupt_errors_total counter",
+                r#"irqtop_interrupt_errors_total{irq="ERR"} 2"#,
+                r#"irqtop_interrupt_errors_total{irq="MIS"} 0"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_cpu_columns_and_idle_rows() {
+        let filter = IrqFilter {
+            cpus: Some("1,3".parse().unwrap()),
+            hide_idle: true,
+            ..IrqFilter::default()
+        };
+        let out = render(write_counters, &filter);
+        let samples: Vec<&str> = out.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            samples,
+            [
+                r#"irqtop_interrupts_total{irq="24",device="nvme0q1",chip="IR-PCI-MSI",cpu="1"} 7"#,
+                r#"irqtop_interrupts_total{irq="24",device="nvme0q1",chip="IR-PCI-MSI",cpu="3"} 0"#,
+                r#"irqtop_interrupts_total{irq="LOC",device="Local timer interrupts",chip="",cpu="1"} 200"#,
+                r#"irqtop_interrupts_total{irq="LOC",device="Local timer interrupts",chip="",cpu="3"} 300"#,
+                r#"irqtop_interrupt_errors_total{irq="ERR"} 2"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_irq_totals_over_selected_cpus() {
+        let filter = IrqFilter {
+            cpus: Some("0-1".parse().unwrap()),
+            ..IrqFilter::default()
+        };
+        let out = render(write_irq_totals, &filter);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# HELP irqtop_irq_interrupts_total Interrupts handled since boot, per IRQ summed over CPUs.",
+                "# TYPE irqtop_irq_interrupts_total counter",
+                r#"irqtop_irq_interrupts_total{irq="24",device="nvme0q1",chip="IR-PCI-MSI"} 12"#,
+                r#"irqtop_irq_interrupts_total{irq="25",device="my\"dev\\x",chip="IR-PCI-MSI"} 0"#,
+                r#"irqtop_irq_interrupts_total{irq="LOC",device="Local timer interrupts",chip=""} 300"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_affinity_info_for_irqs_with_affinity() {
+        let affinity = AffinityMap::from([(IrqId::Numeric(24), "0-3".to_string()), (IrqId::Numeric(25), "1".to_string())]);
+        let effective = AffinityMap::from([(IrqId::Numeric(24), "2".to_string())]);
+        let out = render(
+            |out, s, f| write_affinity_info(out, s, &affinity, &effective, f),
+            &IrqFilter::default(),
+        );
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "# HELP irqtop_irq_affinity_info Configured and effective CPU affinity per IRQ.",
+                "# TYPE irqtop_irq_affinity_info gauge",
+                r#"irqtop_irq_affinity_info{irq="24",device="nvme0q1",affinity="0-3",effective_affinity="2"} 1"#,
+                r#"irqtop_irq_affinity_info{irq="25",device="my\"dev\\x",affinity="1",effective_affinity=""} 1"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(Escaped("a\\b\"c\nd").to_string(), r#"a\\b\"c\nd"#);
+    }
+}
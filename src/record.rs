@@ -0,0 +1,122 @@
+use crate::affinity::AffinityMap;
+use crate::filter::IrqFilter;
+use crate::delta::per_second;
+use crate::interrupts::{IrqCategory, IrqStats};
+use crate::irq_id::IrqId;
+use crate::sampler::Sampler;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Self-contained view of one sampling tick, for exporters.
+///
+/// Combines the sampler's current snapshot and deltas with the affinity
+/// maps, so a record can be serialized or formatted on its own.
+#[derive(Debug, Clone, Serialize)]
+pub struct SampleRecord {
+    /// Wall-clock time of the sample, seconds since the Unix epoch
+    pub timestamp: f64,
+    /// Same instant as `timestamp`, at full precision
+    #[serde(skip)]
+    pub wall_clock: SystemTime,
+    /// Measured time since the previous sample, in seconds
+    pub interval: f64,
+    /// CPU ids, in the order of the `per_cpu` and `counts` lists of every
+    /// IRQ except global error rows ([`IrqCategory::Error`]), whose lists
+    /// hold a single system-wide value
+    pub cpus: Vec<u32>,
+    pub irqs: Vec<IrqRecord>,
+}
+
+/// One IRQ within a [`SampleRecord`]
+#[derive(Debug, Clone, Serialize)]
+pub struct IrqRecord {
+    pub irq: IrqId,
+    pub category: IrqCategory,
+    pub device: String,
+    pub chip: String,
+    pub hwirq: Option<u64>,
+    pub trigger: String,
+    pub actions: Vec<String>,
+    /// Cumulative counter, summed over CPUs
+    pub total: u64,
+    /// Change since the previous sample
+    pub delta: u64,
+    /// `delta` per second
+    pub rate: f64,
+    /// Per-CPU deltas since the previous sample, by
+    /// [`SampleRecord::cpus`]; one system-wide value for error rows
+    pub per_cpu: Vec<u64>,
+    /// Per-CPU cumulative counters, laid out like `per_cpu`
+    pub counts: Vec<u64>,
+    pub affinity: Option<String>,
+    pub effective_affinity: Option<String>,
+}
+
+impl SampleRecord {
+    /// Build a record for every IRQ with a delta, in `/proc/interrupts` order
+    pub fn new(sampler: &Sampler, affinity: &AffinityMap, effective: &AffinityMap) -> Self {
+        Self::filtered(sampler, affinity, effective, &IrqFilter::default())
+    }
+
+    /// Like [`new`](SampleRecord::new), but only with the IRQs `filter`
+    /// keeps, and with per-CPU lists, totals and rates limited to its CPUs
+    pub fn filtered(sampler: &Sampler, affinity: &AffinityMap, effective: &AffinityMap, filter: &IrqFilter) -> Self {
+        let data = sampler.current();
+        let elapsed = sampler.elapsed();
+        let select = |stats: &IrqStats, values: &[u64]| -> Vec<u64> {
+            if stats.is_global() {
+                return values.to_vec();
+            }
+            values.iter()
+                .enumerate()
+                .filter(|(idx, _)| filter.keeps_column(data, *idx))
+                .map(|(_, v)| *v)
+                .collect()
+        };
+        let irqs = sampler
+            .deltas()
+            .iter()
+            .filter_map(|delta| {
+                let stats = data.get(&delta.irq)?;
+                let total = filter.sum_selected(data, stats, &delta.per_cpu);
+                if !filter.keeps(&delta.irq, stats, total) {
+                    return None;
+                }
+                Some(IrqRecord {
+                    irq: delta.irq.clone(),
+                    category: stats.category,
+                    device: stats.device(),
+                    chip: stats.desc.chip.clone(),
+                    hwirq: stats.desc.hwirq,
+                    trigger: stats.desc.trigger.clone(),
+                    actions: stats.desc.actions.clone(),
+                    total: filter.sum_selected(data, stats, &stats.counts),
+                    delta: total,
+                    rate: per_second(total, elapsed),
+                    per_cpu: select(stats, &delta.per_cpu),
+                    counts: select(stats, &stats.counts),
+                    affinity: affinity.get(&delta.irq).cloned(),
+                    effective_affinity: effective.get(&delta.irq).cloned(),
+                })
+            })
+            .collect();
+
+        Self {
+            timestamp: data
+                .wall_clock
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            wall_clock: data.wall_clock,
+            interval: elapsed.as_secs_f64(),
+            cpus: data.cpus.iter().copied().filter(|cpu| filter.has_cpu(*cpu)).collect(),
+            irqs,
+        }
+    }
+
+    /// Keep only the `n` busiest IRQs, highest rate first
+    pub fn retain_top(&mut self, n: usize) {
+        self.irqs.sort_by(|a, b| b.rate.total_cmp(&a.rate).then_with(|| a.irq.cmp(&b.irq)));
+        self.irqs.truncate(n);
+    }
+}
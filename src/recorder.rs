@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use irqtop_rs::capture::CaptureWriter;
+use irqtop_rs::{get_affinity_map, get_effective_affinity_map, InterruptsReader, Roots};
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Record raw `/proc/interrupts` reads and affinity to `path` every
+/// `interval`, for `duration` or until interrupted
+pub fn run(roots: &Roots, path: &Path, duration: Option<Duration>, interval: Duration) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut writer = CaptureWriter::new(BufWriter::new(file))?;
+    let mut reader = InterruptsReader::open(roots)?;
+    eprintln!("Recording to {} every {}ms", path.display(), interval.as_millis());
+
+    let started = Instant::now();
+    let mut next = started;
+    let mut frames = 0;
+    // Scheduled against the start time so long recordings don't drift
+    while duration.is_none_or(|d| next - started <= d) {
+        std::thread::sleep(next.saturating_duration_since(Instant::now()));
+        let interrupts = reader.read_raw()?;
+        let (taken_at, wall_clock) = (Instant::now(), SystemTime::now());
+        let affinity = get_affinity_map(roots);
+        let effective = get_effective_affinity_map(roots);
+        writer
+            .write_frame(taken_at, wall_clock, interrupts, &affinity, &effective)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        frames += 1;
+        next += interval;
+    }
+
+    writer.finish()?.into_inner()?.sync_all()?;
+    eprintln!("Recorded {} frames", frames);
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use anyhow::{bail, Context, Result};
+use irqtop_rs::capture::{CaptureReader, Frame, FrameInfo};
+use irqtop_rs::Sampler;
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 64.0;
+
+/// Playback position in a recorded capture.
+///
+/// The current frame is shown against the one before it, so the position
+/// never goes below 1.
+///
+/// Only an index of the frames is kept in memory; frames are decoded when
+/// shown. Playing forward continues the open stream, while moving back
+/// reopens the capture and skips to the frame.
+pub struct Replay {
+    path: PathBuf,
+    frames: Vec<FrameInfo>,
+    reader: CaptureReader<BufReader<File>>,
+    /// Last frame decoded, which the next one is shown against
+    last: Option<(usize, Frame)>,
+    pos: usize,
+    playing: bool,
+    speed: f64,
+    /// Monotonic base that frame offsets are added to
+    origin: Instant,
+}
+
+impl Replay {
+    pub fn open(path: &Path, speed: f64) -> Result<Self> {
+        if !speed.is_finite() || speed <= 0.0 {
+            bail!("invalid replay speed {} (expected a positive number)", speed);
+        }
+        let mut frames = Vec::new();
+        let mut reader = open(path)?;
+        while let Some(info) = reader.next_info().with_context(|| format!("failed to read {}", path.display()))? {
+            frames.push(info);
+        }
+        if frames.len() < 2 {
+            bail!("{} has fewer than two frames", path.display());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            frames,
+            reader: open(path)?,
+            last: None,
+            pos: 1,
+            playing: true,
+            speed: speed.clamp(MIN_SPEED, MAX_SPEED),
+            origin: Instant::now(),
+        })
+    }
+
+    fn frame(&self) -> &FrameInfo {
+        &self.frames[self.pos]
+    }
+
+    /// Feed the previous and current frame through `sampler` and return
+    /// the current one
+    pub fn load(&mut self, sampler: &mut Sampler) -> Result<&Frame> {
+        for idx in [self.pos - 1, self.pos] {
+            let frame = self.decode(idx)?;
+            sampler.sample_with(|snapshot| {
+                frame.snapshot_into(snapshot, self.origin);
+                Ok(())
+            })?;
+            self.last = Some((idx, frame));
+        }
+        Ok(self.last.as_ref().map(|(_, frame)| frame).expect("frame just decoded"))
+    }
+
+    /// Decode frame `idx`, reusing the last decoded frame
+    fn decode(&mut self, idx: usize) -> Result<Frame> {
+        if let Some((last, frame)) = self.last.take()
+            && last == idx
+        {
+            return Ok(frame);
+        }
+        let position = self.frames[idx].position;
+        if self.reader.position() > position {
+            self.reader = open(&self.path)?;
+        }
+        let context = || format!("failed to read {}", self.path.display());
+        self.reader.skip_to(position).with_context(context)?;
+        self.reader
+            .next_frame()
+            .with_context(context)?
+            .with_context(|| format!("{} changed while replaying", self.path.display()))
+    }
+
+    /// Move to the next frame while playing; returns whether the position changed
+    pub fn advance(&mut self) -> bool {
+        if !self.playing {
+            return false;
+        }
+        if self.pos + 1 == self.frames.len() {
+            self.playing = false;
+            return false;
+        }
+        self.pos += 1;
+        true
+    }
+
+    /// Wall time until the next frame is due at the current speed
+    pub fn next_gap(&self) -> Option<Duration> {
+        let next = self.frames.get(self.pos + 1)?;
+        let gap = next.offset.saturating_sub(self.frame().offset);
+        Some(gap.div_f64(self.speed))
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn toggle_play(&mut self) {
+        // Playing from the last frame restarts the capture
+        if !self.playing && self.pos + 1 == self.frames.len() {
+            self.pos = 1;
+        }
+        self.playing = !self.playing;
+    }
+
+    /// Move `frames` frames forward or back and pause
+    pub fn step(&mut self, frames: isize) {
+        self.playing = false;
+        self.pos = self.pos.saturating_add_signed(frames).clamp(1, self.frames.len() - 1);
+    }
+
+    /// Jump to the first frame at least `secs` of capture time away
+    pub fn seek(&mut self, secs: f64) {
+        let now = self.frame().offset.as_secs_f64();
+        let target = Duration::from_secs_f64((now + secs).max(0.0));
+        let pos = if secs >= 0.0 {
+            self.frames.partition_point(|f| f.offset < target)
+        } else {
+            self.frames.partition_point(|f| f.offset <= target).saturating_sub(1)
+        };
+        self.pos = pos.clamp(1, self.frames.len() - 1);
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    /// Header text replacing the live "Update: … ago"
+    pub fn status(&self) -> String {
+        format!(
+            "Capture: {} | Frame {}/{} | {} {}x",
+            humantime::format_rfc3339_millis(self.frame().wall_clock),
+            self.pos + 1,
+            self.frames.len(),
+            if self.playing { "Playing" } else { "Paused" },
+            self.speed
+        )
+    }
+}
+
+fn open(path: &Path) -> Result<CaptureReader<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    CaptureReader::new(BufReader::new(file)).with_context(|| format!("failed to read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irqtop_rs::capture::CaptureWriter;
+    use irqtop_rs::{AffinityMap, IrqId};
+    use std::time::SystemTime;
+
+    /// Capture of `n` frames 1s apart, where IRQ 24 has fired `i * i` times
+    /// by frame `i`
+    fn write_capture(path: &Path, n: u64) {
+        let mut writer = CaptureWriter::new(File::create(path).unwrap()).unwrap();
+        let start = Instant::now();
+        for i in 0..n {
+            let interrupts = format!("           CPU0\n 24: {:10}   IR-PCI-MSI 524288-edge      nvme0q1\n", i * i);
+            let affinity = AffinityMap::from([(IrqId::Numeric(24), i.to_string())]);
+            let at = Duration::from_secs(i);
+            writer
+                .write_frame(start + at, SystemTime::UNIX_EPOCH + at, interrupts.as_bytes(), &affinity, &affinity)
+                .unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn decodes_frames_on_demand() {
+        let path = std::env::temp_dir().join(format!("irqtop-rs-replay-{}.cap", std::process::id()));
+        write_capture(&path, 30);
+        let mut replay = Replay::open(&path, 1.0).unwrap();
+        let mut sampler = Sampler::new(Default::default());
+        let mut shown = |replay: &mut Replay| {
+            let frame = replay.load(&mut sampler).unwrap();
+            let affinity = frame.affinity[&IrqId::Numeric(24)].clone();
+            (sampler.delta(&IrqId::Numeric(24)).unwrap().total, affinity)
+        };
+
+        assert_eq!(shown(&mut replay), (1, "1".to_string()));
+        assert!(replay.advance());
+        assert_eq!(shown(&mut replay), (3, "2".to_string()));
+        replay.seek(10.0);
+        assert_eq!(shown(&mut replay), (23, "12".to_string()));
+        replay.step(-1);
+        assert_eq!(shown(&mut replay), (21, "11".to_string()));
+        replay.step(100);
+        assert_eq!(shown(&mut replay), (57, "29".to_string()));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_speeds() {
+        for speed in [f64::NAN, f64::INFINITY, 0.0, -1.0] {
+            let err = Replay::open(Path::new("/nonexistent"), speed).err().unwrap();
+            assert!(err.to_string().contains("invalid replay speed"), "{}", err);
+        }
+    }
+}
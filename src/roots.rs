@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+/// Where the procfs and sysfs trees live.
+///
+/// Every reader in this crate resolves its paths through a `Roots`, so the
+/// whole library can be pointed at a captured copy of `/proc` and `/sys`
+/// instead of the live system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roots {
+    pub proc: PathBuf,
+    pub sys: PathBuf,
+}
+
+impl Default for Roots {
+    fn default() -> Self {
+        Self {
+            proc: PathBuf::from("/proc"),
+            sys: PathBuf::from("/sys"),
+        }
+    }
+}
+
+impl Roots {
+    pub fn new(proc: impl Into<PathBuf>, sys: impl Into<PathBuf>) -> Self {
+        Self {
+            proc: proc.into(),
+            sys: sys.into(),
+        }
+    }
+
+    /// Resolve a path relative to the procfs root, e.g. `proc_path("interrupts")`
+    pub fn proc_path(&self, rel: impl AsRef<Path>) -> PathBuf {
+        self.proc.join(rel)
+    }
+
+    /// Resolve a path relative to the sysfs root
+    pub fn sys_path(&self, rel: impl AsRef<Path>) -> PathBuf {
+        self.sys.join(rel)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::delta::{calculate_delta_into, IrqDelta};
+use crate::interrupts::{InterruptsReader, Snapshot};
+use crate::irq_id::IrqId;
+use crate::roots::Roots;
+use anyhow::Result;
+use std::time::Duration;
+
+/// Keeps the previous and current snapshot and the deltas between them.
+///
+/// Each [`sample`](Sampler::sample) swaps the current snapshot into the
+/// previous slot instead of cloning it, re-reads `/proc/interrupts` into the
+/// stale one through a persistent [`InterruptsReader`], then computes totals
+/// and per-CPU deltas in a single pass. Once the IRQ set is stable a sample
+/// does no heap allocation.
+#[derive(Debug)]
+pub struct Sampler {
+    roots: Roots,
+    reader: Option<InterruptsReader>,
+    prev: Snapshot,
+    curr: Snapshot,
+    deltas: Vec<IrqDelta>,
+}
+
+impl Sampler {
+    pub fn new(roots: Roots) -> Self {
+        Self {
+            roots,
+            reader: None,
+            prev: Snapshot::default(),
+            curr: Snapshot::default(),
+            deltas: Vec::new(),
+        }
+    }
+
+    /// Read a new snapshot and recompute the deltas against the last one.
+    ///
+    /// After the first call every IRQ seen so far has no previous value, so
+    /// [`deltas`](Sampler::deltas) stays empty until the second call.
+    pub fn sample(&mut self) -> Result<()> {
+        let reader = match &mut self.reader {
+            Some(reader) => reader,
+            None => self.reader.insert(InterruptsReader::open(&self.roots)?),
+        };
+        Self::rotate(&mut self.prev, &mut self.curr, &mut self.deltas, |curr| reader.read_into(curr))
+    }
+
+    /// Like [`sample`](Sampler::sample), but let `fill` produce the new
+    /// snapshot instead of reading `/proc/interrupts`, e.g. from a
+    /// [`capture`](crate::capture) frame
+    pub fn sample_with(&mut self, fill: impl FnOnce(&mut Snapshot) -> Result<()>) -> Result<()> {
+        Self::rotate(&mut self.prev, &mut self.curr, &mut self.deltas, fill)
+    }
+
+    fn rotate(
+        prev: &mut Snapshot,
+        curr: &mut Snapshot,
+        deltas: &mut Vec<IrqDelta>,
+        fill: impl FnOnce(&mut Snapshot) -> Result<()>,
+    ) -> Result<()> {
+        std::mem::swap(prev, curr);
+        if let Err(err) = fill(curr) {
+            std::mem::swap(prev, curr);
+            return Err(err);
+        }
+        calculate_delta_into(prev, curr, deltas);
+        Ok(())
+    }
+
+    pub fn roots(&self) -> &Roots {
+        &self.roots
+    }
+
+    pub fn current(&self) -> &Snapshot {
+        &self.curr
+    }
+
+    pub fn previous(&self) -> &Snapshot {
+        &self.prev
+    }
+
+    /// Deltas between the previous and current snapshot, in the current
+    /// snapshot's file order, for IRQs present in both
+    pub fn deltas(&self) -> &[IrqDelta] {
+        &self.deltas
+    }
+
+    pub fn delta(&self, irq: &IrqId) -> Option<&IrqDelta> {
+        self.deltas.iter().find(|d| &d.irq == irq)
+    }
+
+    /// Actual time between the previous and current snapshot
+    pub fn elapsed(&self) -> Duration {
+        self.curr.elapsed_since(&self.prev)
+    }
+}
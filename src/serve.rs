@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use irqtop_rs::{prometheus, InterruptsReader, IrqFilter, Roots, Snapshot};
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Serve `/metrics` in Prometheus text format, re-reading
+/// `/proc/interrupts` on every scrape
+pub fn run(roots: &Roots, listen: SocketAddr, filter: &IrqFilter) -> Result<()> {
+    let listener = TcpListener::bind(listen).with_context(|| format!("failed to listen on {}", listen))?;
+    let mut reader = InterruptsReader::open(roots)?;
+    let mut snapshot = Snapshot::default();
+    eprintln!("Serving metrics on http://{}/metrics", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        // One bad client must not take the exporter down
+        if let Err(err) = handle(stream, &mut reader, &mut snapshot, filter) {
+            eprintln!("Request failed: {:#}", err);
+        }
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, reader: &mut InterruptsReader, snapshot: &mut Snapshot, filter: &IrqFilter) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut conn = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    conn.read_line(&mut request_line)?;
+    // Drain the headers; the request body, if any, is ignored
+    let mut header = String::new();
+    while conn.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or("");
+    let mut stream = conn.into_inner();
+
+    match (method, path) {
+        ("GET", "/metrics") => {
+            reader.read_into(snapshot)?;
+            let mut body = Vec::with_capacity(64 * 1024);
+            prometheus::write_counters(&mut body, snapshot, filter)?;
+            respond(&mut stream, "200 OK", prometheus::CONTENT_TYPE, &body)?;
+        }
+        ("GET", "/") => {
+            let body = b"<html><body><a href=\"/metrics\">Metrics</a></body></html>\n";
+            respond(&mut stream, "200 OK", "text/html; charset=utf-8", body)?;
+        }
+        ("GET", _) => respond(&mut stream, "404 Not Found", "text/plain", b"Not Found\n")?,
+        _ => respond(&mut stream, "405 Method Not Allowed", "text/plain", b"Method Not Allowed\n")?,
+    }
+    Ok(())
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use irqtop_rs::capture::{self, Frame};
+use irqtop_rs::{
+    calculate_delta, get_affinity_map, get_effective_affinity_map, InterruptsReader, IrqId, Roots, Snapshot,
+};
+
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Write the current interrupt counters and affinity to stdout as a text snapshot
+pub fn dump(roots: &Roots) -> Result<()> {
+    let mut reader = InterruptsReader::open(roots)?;
+    let frame = Frame {
+        interrupts: reader.read_raw()?.to_vec(),
+        wall_clock: SystemTime::now(),
+        offset: Duration::ZERO,
+        affinity: get_affinity_map(roots),
+        effective_affinity: get_effective_affinity_map(roots),
+    };
+    let mut out = io::stdout().lock();
+    capture::write_text(&mut out, &frame)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Print what changed between two text snapshots
+pub fn diff(before: &Path, after: &Path) -> Result<()> {
+    let (before, after) = (load(before)?, load(after)?);
+    let elapsed = after.wall_clock.duration_since(before.wall_clock).unwrap_or_default();
+    let origin = Instant::now();
+    let mut old = Snapshot::default();
+    let mut new = Snapshot::default();
+    before.snapshot_into(&mut old, origin);
+    after.snapshot_into(&mut new, origin + elapsed);
+
+    match write_diff(&mut io::stdout().lock(), &before, &after, &old, &new, elapsed) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        res => Ok(res?),
+    }
+}
+
+fn load(path: &Path) -> Result<Frame> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    capture::read_text(BufReader::new(file)).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn write_diff(
+    out: &mut impl Write,
+    before: &Frame,
+    after: &Frame,
+    old: &Snapshot,
+    new: &Snapshot,
+    elapsed: Duration,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} -> {} ({:.3}s)",
+        humantime::format_rfc3339_millis(before.wall_clock),
+        humantime::format_rfc3339_millis(after.wall_clock),
+        elapsed.as_secs_f64()
+    )?;
+    // Deltas match columns by CPU id; CPUs on one side only have no baseline
+    let offline: Vec<String> = old.cpus.iter().filter(|c| !new.cpus.contains(c)).map(|c| format!("CPU{}", c)).collect();
+    let online: Vec<String> = new.cpus.iter().filter(|c| !old.cpus.contains(c)).map(|c| format!("CPU{}", c)).collect();
+    if !offline.is_empty() {
+        writeln!(out, "\nCPUs only before: {}", offline.join(" "))?;
+    }
+    if !online.is_empty() {
+        writeln!(out, "\nCPUs only after: {}", online.join(" "))?;
+    }
+
+    let appeared: Vec<_> = new.iter().filter(|(irq, _)| old.get(irq).is_none()).collect();
+    let disappeared: Vec<_> = old.iter().filter(|(irq, _)| new.get(irq).is_none()).collect();
+    if !appeared.is_empty() {
+        writeln!(out, "\nAppeared:")?;
+        for (irq, stats) in appeared {
+            writeln!(out, "  {:>6}  {:>12}  {}", irq.to_string(), stats.total(), stats.device())?;
+        }
+    }
+    if !disappeared.is_empty() {
+        writeln!(out, "\nDisappeared:")?;
+        for (irq, stats) in disappeared {
+            writeln!(out, "  {:>6}  {:>12}  {}", irq.to_string(), stats.total(), stats.device())?;
+        }
+    }
+
+    let mut irqs: Vec<&IrqId> = before.affinity.keys().chain(after.affinity.keys())
+        .chain(before.effective_affinity.keys())
+        .chain(after.effective_affinity.keys())
+        .collect();
+    irqs.sort();
+    irqs.dedup();
+    let mut header = false;
+    for irq in irqs {
+        let aff = (before.affinity.get(irq), after.affinity.get(irq));
+        let eff = (before.effective_affinity.get(irq), after.effective_affinity.get(irq));
+        // Only IRQs present on both sides; appearing ones were listed above
+        if old.get(irq).is_none() || new.get(irq).is_none() || (aff.0 == aff.1 && eff.0 == eff.1) {
+            continue;
+        }
+        if !header {
+            writeln!(out, "\nAffinity changes:")?;
+            header = true;
+        }
+        let show = |v: Option<&String>| v.map_or("N/A".to_string(), String::clone);
+        write!(out, "  {:>6} ", irq.to_string())?;
+        if aff.0 != aff.1 {
+            write!(out, " affinity {} -> {}", show(aff.0), show(aff.1))?;
+        }
+        if eff.0 != eff.1 {
+            write!(out, " effective {} -> {}", show(eff.0), show(eff.1))?;
+        }
+        writeln!(out)?;
+    }
+
+    let mut deltas = calculate_delta(old, new);
+    deltas.retain(|d| d.total > 0);
+    deltas.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.irq.cmp(&b.irq)));
+    writeln!(out, "\nCount changes:")?;
+    writeln!(out, "  {:>6}  {:>12}  {:>12}  Device", "IRQ", "Delta", "Rate/s")?;
+    for delta in &deltas {
+        let Some(stats) = new.get(&delta.irq) else {
+            continue;
+        };
+        writeln!(
+            out,
+            "  {:>6}  {:>12}  {:>12.1}  {}",
+            delta.irq.to_string(),
+            delta.total,
+            delta.rate,
+            stats.device()
+        )?;
+        if stats.is_global() {
+            continue;
+        }
+        let per_cpu: Vec<String> = delta.per_cpu.iter()
+            .enumerate()
+            .filter(|(_, d)| **d > 0)
+            .map(|(idx, d)| format!("CPU{}:{}", new.cpu_id(idx), d))
+            .collect();
+        writeln!(out, "  {:>6}  {}", "", per_cpu.join(" "))?;
+    }
+
+    let mut cpu_totals = vec![0u64; new.cpus.len()];
+    for delta in deltas.iter().filter(|d| new.get(&d.irq).is_some_and(|s| !s.is_global())) {
+        for (total, d) in cpu_totals.iter_mut().zip(&delta.per_cpu) {
+            *total += d;
+        }
+    }
+    writeln!(out, "\nPer-CPU totals:")?;
+    for (idx, total) in cpu_totals.iter().enumerate() {
+        writeln!(out, "  {:>6}  {:>12}", format!("CPU{}", new.cpu_id(idx)), total)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use irqtop_rs::{per_second, IrqFilter, Sampler};
+
+use std::io::{self, Write};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// Keep datagrams under a typical Ethernet MTU so relays never see fragments
+const MAX_DATAGRAM: usize = 1432;
+
+/// Push every IRQ's rate as a StatsD gauge `<prefix>.<irq>:<rate>|g` once
+/// per interval.
+///
+/// Gauges are packed several to a datagram, newline-separated. Only IRQs
+/// that `filter` keeps are sent, with rates over its CPUs.
+pub fn run(mut sampler: Sampler, target: &str, prefix: &str, filter: &IrqFilter, interval: Duration) -> Result<()> {
+    let addr = target
+        .to_socket_addrs()
+        .with_context(|| format!("failed to resolve {}", target))?
+        .next()
+        .with_context(|| format!("no address for {}", target))?;
+    let bind: SocketAddr = if addr.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+    let socket = UdpSocket::bind(bind)?;
+    socket.connect(addr)?;
+
+    let mut packer = Packer::default();
+    sampler.sample()?;
+    loop {
+        std::thread::sleep(interval);
+        sampler.sample()?;
+        packer.pack(&sampler, prefix, filter, |packet| send(&socket, packet))?;
+    }
+}
+
+/// Buffers for packing gauges into datagrams, reused across intervals
+#[derive(Default)]
+struct Packer {
+    packet: Vec<u8>,
+    line: Vec<u8>,
+}
+
+impl Packer {
+    /// Pass the gauges for the sampler's deltas to `send`, packed into
+    /// datagrams of at most [`MAX_DATAGRAM`] bytes.
+    ///
+    /// The trailing newline is optional and some older relays reject it, so
+    /// each datagram goes out without one.
+    fn pack(&mut self, sampler: &Sampler, prefix: &str, filter: &IrqFilter, mut send: impl FnMut(&[u8])) -> io::Result<()> {
+        let data = sampler.current();
+        for delta in sampler.deltas() {
+            let Some(stats) = data.get(&delta.irq) else {
+                continue;
+            };
+            let total = filter.sum_selected(data, stats, &delta.per_cpu);
+            if !filter.keeps(&delta.irq, stats, total) {
+                continue;
+            }
+            self.line.clear();
+            writeln!(self.line, "{}.{}:{:.2}|g", prefix, delta.irq, per_second(total, sampler.elapsed()))?;
+            if self.packet.len() + self.line.len() > MAX_DATAGRAM {
+                self.flush(&mut send);
+            }
+            self.packet.extend_from_slice(&self.line);
+        }
+        self.flush(&mut send);
+        Ok(())
+    }
+
+    fn flush(&mut self, send: &mut impl FnMut(&[u8])) {
+        if let Some((b'\n', packet)) = self.packet.split_last() {
+            send(packet);
+        }
+        self.packet.clear();
+    }
+}
+
+fn send(socket: &UdpSocket, packet: &[u8]) {
+    match socket.send(packet) {
+        // A relay that is restarting shows up as ECONNREFUSED on the next send
+        Err(err) if err.kind() != io::ErrorKind::ConnectionRefused => eprintln!("StatsD send failed: {}", err),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use irqtop_rs::{parse_interrupts_into, Roots};
+
+    /// Sampler whose deltas go from all-zero counters to `irq * 2` on CPU0
+    /// and 1 on CPU1 for IRQs 0..`irqs`, one second apart
+    fn sampler(irqs: u32) -> Sampler {
+        let frame = |scale: u64| {
+            let mut text = String::from("           CPU0       CPU1\n");
+            for irq in 0..irqs {
+                text += &format!("{:4}: {:10} {:10}  IR-PCI-MSI {}-edge      dev{}\n", irq, irq as u64 * 2 * scale, scale, irq, irq);
+            }
+            text
+        };
+        let mut sampler = Sampler::new(Roots::default());
+        sampler
+            .sample_with(|s| {
+                parse_interrupts_into(frame(0).as_bytes(), s);
+                Ok(())
+            })
+            .unwrap();
+        let start = sampler.current().taken_at;
+        sampler
+            .sample_with(|s| {
+                parse_interrupts_into(frame(1).as_bytes(), s);
+                s.taken_at = start + Duration::from_secs(1);
+                Ok(())
+            })
+            .unwrap();
+        sampler
+    }
+
+    fn pack(sampler: &Sampler, filter: &IrqFilter) -> Vec<String> {
+        let mut packets = Vec::new();
+        Packer::default()
+            .pack(sampler, "irq", filter, |p| packets.push(String::from_utf8(p.to_vec()).unwrap()))
+            .unwrap();
+        packets
+    }
+
+    #[test]
+    fn writes_gauges_without_trailing_newline() {
+        let packets = pack(&sampler(3), &IrqFilter::default());
+        assert_eq!(packets, ["irq.0:1.00|g\nirq.1:3.00|g\nirq.2:5.00|g"]);
+    }
+
+    #[test]
+    fn rates_cover_the_filtered_cpus() {
+        let filter = IrqFilter {
+            irqs: Some("1-2".parse().unwrap()),
+            cpus: Some("1".parse().unwrap()),
+            ..IrqFilter::default()
+        };
+        assert_eq!(pack(&sampler(3), &filter), ["irq.1:1.00|g\nirq.2:1.00|g"]);
+    }
+
+    #[test]
+    fn splits_gauges_across_datagrams() {
+        let packets = pack(&sampler(300), &IrqFilter::default());
+        assert!(packets.len() > 1);
+        for packet in &packets {
+            assert!(packet.len() <= MAX_DATAGRAM, "{} bytes", packet.len());
+            assert!(!packet.ends_with('\n'));
+        }
+        let gauges: Vec<&str> = packets.iter().flat_map(|p| p.lines()).collect();
+        assert_eq!(gauges.len(), 300);
+        assert_eq!(gauges[0], "irq.0:1.00|g");
+        assert_eq!(gauges[299], "irq.299:599.00|g");
+    }
+
+    #[test]
+    fn skips_empty_packets() {
+        let filter = IrqFilter {
+            irqs: Some("1000".parse().unwrap()),
+            ..IrqFilter::default()
+        };
+        assert!(pack(&sampler(3), &filter).is_empty());
+    }
+}
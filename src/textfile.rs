@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use irqtop_rs::{
+    get_affinity_map, get_effective_affinity_map, prometheus, InterruptsReader, IrqFilter, Roots, Snapshot,
+};
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+/// Periodically write counters and affinity info to `dir/name` for the
+/// node_exporter textfile collector.
+///
+/// Each update goes to a temporary file in the same directory that is then
+/// renamed over the target, so the collector never sees a partial file.
+pub fn run(roots: &Roots, dir: &Path, name: &str, interval: Duration, filter: &IrqFilter) -> Result<()> {
+    let target = dir.join(name);
+    // No `.prom` suffix, so the collector skips it while it is being written
+    let tmp = dir.join(format!(".{}.{}.tmp", name, std::process::id()));
+    let mut reader = InterruptsReader::open(roots)?;
+    let mut snapshot = Snapshot::default();
+
+    loop {
+        reader.read_into(&mut snapshot)?;
+        let affinity = get_affinity_map(roots);
+        let effective = get_effective_affinity_map(roots);
+
+        let mut out = BufWriter::new(
+            File::create(&tmp).with_context(|| format!("failed to create {}", tmp.display()))?,
+        );
+        prometheus::write_counters(&mut out, &snapshot, filter)?;
+        prometheus::write_irq_totals(&mut out, &snapshot, filter)?;
+        prometheus::write_affinity_info(&mut out, &snapshot, &affinity, &effective, filter)?;
+        out.into_inner()?.sync_all()?;
+        fs::rename(&tmp, &target)
+            .with_context(|| format!("failed to rename {} to {}", tmp.display(), target.display()))?;
+
+        std::thread::sleep(interval);
+    }
+}
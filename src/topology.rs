@@ -0,0 +1,238 @@
+use crate::interrupts::Snapshot;
+use crate::roots::Roots;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+/// Socket and core of one CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CpuPlace {
+    /// `physical_package_id`
+    pub package: u32,
+    pub core: u32,
+}
+
+/// CPU topology from `/sys/devices/system/cpu/cpuN/topology`
+#[derive(Debug, Clone, Default)]
+pub struct CpuTopology {
+    places: HashMap<u32, CpuPlace>,
+}
+
+impl CpuTopology {
+    /// Read the socket and core of each of `cpus`; CPUs whose topology
+    /// files are missing are left out
+    pub fn read(roots: &Roots, cpus: impl IntoIterator<Item = u32>) -> Self {
+        let read_id = |cpu: u32, file: &str| {
+            fs::read_to_string(roots.sys_path(format!("devices/system/cpu/cpu{}/topology/{}", cpu, file)))
+                .ok()
+                .and_then(|s| s.trim().parse::<u32>().ok())
+        };
+        let places = cpus
+            .into_iter()
+            .filter_map(|cpu| {
+                let package = read_id(cpu, "physical_package_id")?;
+                let core = read_id(cpu, "core_id")?;
+                Some((cpu, CpuPlace { package, core }))
+            })
+            .collect();
+        Self { places }
+    }
+
+    pub fn place(&self, cpu: u32) -> Option<CpuPlace> {
+        self.places.get(&cpu).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.places.is_empty()
+    }
+
+    /// Group the count `columns` of `snapshot` into buckets.
+    ///
+    /// Core and socket buckets are ordered by socket and core id. A CPU
+    /// without topology stays in a bucket of its own, after the others.
+    pub fn buckets(&self, snapshot: &Snapshot, columns: &[usize], grouping: CpuGrouping) -> Vec<CpuBucket> {
+        let single = |idx: usize| CpuBucket {
+            label: snapshot.cpu_id(idx).to_string(),
+            columns: vec![idx],
+        };
+        match grouping {
+            CpuGrouping::Cpu => columns.iter().map(|&idx| single(idx)).collect(),
+            CpuGrouping::Group(n) => columns
+                .chunks(n.max(1))
+                .map(|chunk| CpuBucket {
+                    label: snapshot.cpu_id(chunk[0]).to_string(),
+                    columns: chunk.to_vec(),
+                })
+                .collect(),
+            CpuGrouping::Core | CpuGrouping::Socket => {
+                let sockets = self.places.values().map(|p| p.package).max().unwrap_or(0) + 1;
+                let mut groups: BTreeMap<CpuPlace, Vec<usize>> = BTreeMap::new();
+                let mut unknown = Vec::new();
+                for &idx in columns {
+                    match self.place(snapshot.cpu_id(idx)) {
+                        Some(place) if grouping == CpuGrouping::Socket => {
+                            groups.entry(CpuPlace { core: 0, ..place }).or_default().push(idx)
+                        }
+                        Some(place) => groups.entry(place).or_default().push(idx),
+                        None => unknown.push(single(idx)),
+                    }
+                }
+                groups
+                    .into_iter()
+                    .map(|(place, columns)| CpuBucket {
+                        label: match grouping {
+                            CpuGrouping::Socket => format!("s{}", place.package),
+                            _ if sockets > 1 => format!("s{}c{}", place.package, place.core),
+                            _ => format!("c{}", place.core),
+                        },
+                        columns,
+                    })
+                    .chain(unknown)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// How CPU columns are merged when there are too many to show one by one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuGrouping {
+    Cpu,
+    /// Hyperthread siblings together
+    Core,
+    Socket,
+    /// Runs of N consecutive CPUs
+    Group(usize),
+}
+
+impl FromStr for CpuGrouping {
+    type Err = anyhow::Error;
+
+    /// `cpu`, `core`, `socket` or a group size
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "cpu" => CpuGrouping::Cpu,
+            "core" => CpuGrouping::Core,
+            "socket" => CpuGrouping::Socket,
+            _ => match s.parse::<usize>() {
+                Ok(n) if n > 0 => CpuGrouping::Group(n),
+                _ => bail!("invalid CPU grouping {:?} (expected cpu, core, socket or a group size)", s),
+            },
+        })
+    }
+}
+
+impl fmt::Display for CpuGrouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpuGrouping::Cpu => f.write_str("cpu"),
+            CpuGrouping::Core => f.write_str("core"),
+            CpuGrouping::Socket => f.write_str("socket"),
+            CpuGrouping::Group(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Count columns shown as one heatmap column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuBucket {
+    /// CPU id, `c<core>` / `s<socket>c<core>`, `s<socket>`, or the first CPU of a group
+    pub label: String,
+    pub columns: Vec<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interrupts::parse_interrupts;
+    use std::path::Path;
+
+    /// (cpu, socket, core): two sockets with hyperthread pairs, CPU6 without topology
+    const PLACES: &[(u32, u32, u32)] = &[(0, 0, 0), (1, 0, 1), (2, 0, 0), (3, 0, 1), (4, 1, 0), (5, 1, 0)];
+
+    fn write_topology(dir: &Path) {
+        for &(cpu, package, core) in PLACES {
+            let topology = dir.join(format!("devices/system/cpu/cpu{}/topology", cpu));
+            fs::create_dir_all(&topology).unwrap();
+            fs::write(topology.join("physical_package_id"), format!("{}\n", package)).unwrap();
+            fs::write(topology.join("core_id"), format!("{}\n", core)).unwrap();
+        }
+    }
+
+    fn snapshot() -> Snapshot {
+        parse_interrupts(
+            b"           CPU0       CPU1       CPU2       CPU3       CPU4       CPU5       CPU6
+  24:          1          1          1          1          1          1          1   IR-PCI-MSI 524288-edge      nvme0q1
+",
+        )
+    }
+
+    fn buckets(topology: &CpuTopology, columns: &[usize], grouping: CpuGrouping) -> Vec<(String, Vec<usize>)> {
+        topology
+            .buckets(&snapshot(), columns, grouping)
+            .into_iter()
+            .map(|b| (b.label, b.columns))
+            .collect()
+    }
+
+    fn bucket(label: &str, columns: &[usize]) -> (String, Vec<usize>) {
+        (label.to_string(), columns.to_vec())
+    }
+
+    #[test]
+    fn groups_by_topology() {
+        let dir = std::env::temp_dir().join(format!("irqtop-rs-topology-{}", std::process::id()));
+        write_topology(&dir);
+        let roots = Roots::new("/nonexistent", &dir);
+        let topology = CpuTopology::read(&roots, 0..7);
+        let one_socket = CpuTopology::read(&roots, 0..4);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(topology.place(3), Some(CpuPlace { package: 0, core: 1 }));
+        assert_eq!(topology.place(6), None);
+        let all: Vec<usize> = (0..7).collect();
+
+        assert_eq!(
+            buckets(&topology, &all, CpuGrouping::Core),
+            [bucket("s0c0", &[0, 2]), bucket("s0c1", &[1, 3]), bucket("s1c0", &[4, 5]), bucket("6", &[6])]
+        );
+        assert_eq!(
+            buckets(&topology, &all, CpuGrouping::Socket),
+            [bucket("s0", &[0, 1, 2, 3]), bucket("s1", &[4, 5]), bucket("6", &[6])]
+        );
+        // Single-socket labels leave out the socket; CPUs without topology come last
+        assert_eq!(
+            buckets(&one_socket, &[6, 0, 4, 1, 2], CpuGrouping::Core),
+            [bucket("c0", &[0, 2]), bucket("c1", &[1]), bucket("6", &[6]), bucket("4", &[4])]
+        );
+    }
+
+    #[test]
+    fn groups_selected_columns_in_runs() {
+        let topology = CpuTopology::default();
+        // --cpu 0,1,3,4,6
+        let columns = [0, 1, 3, 4, 6];
+        assert_eq!(
+            buckets(&topology, &columns, CpuGrouping::Group(2)),
+            [bucket("0", &[0, 1]), bucket("3", &[3, 4]), bucket("6", &[6])]
+        );
+        assert_eq!(
+            buckets(&topology, &columns, CpuGrouping::Cpu),
+            [bucket("0", &[0]), bucket("1", &[1]), bucket("3", &[3]), bucket("4", &[4]), bucket("6", &[6])]
+        );
+        // Without topology, core grouping falls back to one bucket per CPU
+        assert_eq!(buckets(&topology, &columns[..2], CpuGrouping::Core), [bucket("0", &[0]), bucket("1", &[1])]);
+    }
+
+    #[test]
+    fn parses_grouping() {
+        for text in ["cpu", "core", "socket", "8"] {
+            assert_eq!(text.parse::<CpuGrouping>().unwrap().to_string(), text);
+        }
+        assert_eq!("8".parse::<CpuGrouping>().unwrap(), CpuGrouping::Group(8));
+        assert!("0".parse::<CpuGrouping>().is_err());
+        assert!("numa".parse::<CpuGrouping>().is_err());
+    }
+}
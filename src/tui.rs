@@ -0,0 +1,1294 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use irqtop_rs::{
+    get_affinity_map, get_effective_affinity_map, per_second, AffinityMap, CpuGrouping, CpuList, CpuTopology,
+    IrqCategory, IrqDelta, IrqFilter, IrqId, RateHistory, Roots, Sampler, Search,
+};
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::Span,
+    widgets::{Axis, Block, Borders, Cell, Chart, Clear, Dataset, GraphType, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+
+use crate::replay::Replay;
+
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Samples of rate history kept unless `--history` says otherwise
+pub const DEFAULT_HISTORY: usize = 60;
+/// Width of the table's sparkline column, in samples
+const SPARKLINE_WIDTH: usize = 16;
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Heatmap colors from coolest to hottest (xterm 256-color indices)
+const HEAT_COLORS: [u8; 12] = [17, 19, 25, 31, 37, 43, 118, 190, 226, 214, 208, 196];
+/// Group size `g` cycles to when `--heatmap-group` doesn't set one
+const DEFAULT_GROUP_SIZE: usize = 8;
+
+/// Application state
+pub struct App {
+    sampler: Sampler,
+    source: Source,
+    /// Rows of the sampler's deltas that pass the category toggles and the
+    /// filter, in display order
+    deltas: Vec<(IrqId, u64)>,
+    filter: IrqFilter,
+    /// Recent rates behind the sparklines and the detail chart
+    history: RateHistory,
+    /// Text of the `/` prompt while it is open
+    search_input: Option<String>,
+    /// Search to restore when the prompt is cancelled
+    search_backup: Option<Search>,
+    /// Why the prompt's text doesn't parse; the last valid search stays applied
+    search_error: Option<String>,
+    affinity_map: AffinityMap,
+    effective_affinity_map: AffinityMap,
+    selected_row: usize,
+    sort_by: SortBy,
+    rate_mode: RateMode,
+    hidden_categories: HashSet<IrqCategory>,
+    show_help: bool,
+    show_irq_detail: bool,
+    detail_irq_name: Option<IrqId>,
+    detail_scroll_offset: usize,
+    show_heatmap: bool,
+    grouping: CpuGrouping,
+    /// Size of the `CpuGrouping::Group` step in the `g` cycle
+    group_size: usize,
+    /// Socket and core of each CPU, read when the heatmap opens
+    topology: CpuTopology,
+    /// First heatmap column shown
+    heatmap_offset: usize,
+    show_cpus: bool,
+    /// Selected row of the CPU list
+    cpu_row: usize,
+    /// Count column of the CPU drilled into, with its selected IRQ row
+    cpu_detail: Option<(usize, usize)>,
+    running: bool,
+    last_update: Instant,
+}
+
+/// Where [`App::update_data`] takes its samples from
+enum Source {
+    /// `/proc/interrupts` and `/proc/irq` under the sampler's roots
+    Live,
+    /// Frames of a recorded capture
+    Replay(Box<Replay>),
+}
+
+/// How deltas are shown in the table and detail view
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum RateMode {
+    /// Scaled to events per second over the measured elapsed time
+    PerSecond,
+    /// Raw counter change since the previous sample
+    PerInterval,
+}
+
+#[derive(PartialEq, Eq)]
+enum SortBy {
+    Irq,
+    Delta,
+    Affinity,
+    EffectiveAffinity,
+    Chip,
+    Hwirq,
+    Trigger,
+    Device,
+}
+
+impl App {
+    pub fn new(roots: Roots) -> Self {
+        Self::with_source(Sampler::new(roots), Source::Live)
+    }
+
+    /// Play back a capture instead of sampling the live system
+    pub fn replay(replay: Replay) -> Self {
+        Self::with_source(Sampler::new(Roots::default()), Source::Replay(Box::new(replay)))
+    }
+
+    fn with_source(sampler: Sampler, source: Source) -> Self {
+        Self {
+            sampler,
+            source,
+            deltas: Vec::new(),
+            filter: IrqFilter::default(),
+            history: RateHistory::new(DEFAULT_HISTORY),
+            search_input: None,
+            search_backup: None,
+            search_error: None,
+            affinity_map: AffinityMap::new(),
+            effective_affinity_map: AffinityMap::new(),
+            selected_row: 0,
+            sort_by: SortBy::Delta,
+            rate_mode: RateMode::PerSecond,
+            hidden_categories: HashSet::new(),
+            show_help: false,
+            show_irq_detail: false,
+            detail_irq_name: None,
+            detail_scroll_offset: 0,
+            show_heatmap: false,
+            grouping: CpuGrouping::Cpu,
+            group_size: DEFAULT_GROUP_SIZE,
+            topology: CpuTopology::default(),
+            heatmap_offset: 0,
+            show_cpus: false,
+            cpu_row: 0,
+            cpu_detail: None,
+            running: true,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Restrict rows and CPUs; `filter.search` fills the `/` prompt
+    pub fn set_filter(&mut self, filter: IrqFilter) {
+        self.filter = filter;
+    }
+
+    /// Keep the last `depth` samples of rate history
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history = RateHistory::new(depth);
+    }
+
+    /// How the heatmap merges CPU columns
+    pub fn set_heatmap_grouping(&mut self, grouping: CpuGrouping) {
+        if let CpuGrouping::Group(n) = grouping {
+            self.group_size = n;
+        }
+        self.grouping = grouping;
+    }
+
+    /// Show the per-CPU detail view of `irq`
+    pub fn open_detail(&mut self, irq: IrqId) {
+        self.detail_irq_name = Some(irq);
+        self.show_irq_detail = true;
+        self.detail_scroll_offset = 0;
+    }
+
+    /// Take a new live sample, or load the replay's current frame
+    pub fn update_data(&mut self) -> Result<()> {
+        match &mut self.source {
+            Source::Live => {
+                self.sampler.sample()?;
+                self.affinity_map = get_affinity_map(self.sampler.roots());
+                self.effective_affinity_map = get_effective_affinity_map(self.sampler.roots());
+            }
+            Source::Replay(replay) => {
+                let frame = replay.load(&mut self.sampler)?;
+                self.affinity_map = frame.affinity.clone();
+                self.effective_affinity_map = frame.effective_affinity.clone();
+            }
+        }
+        self.history.push(&self.sampler, &self.filter);
+        self.last_update = Instant::now();
+        
+        Ok(())
+    }
+
+    /// Whether the next tick has new data: always when live, only while
+    /// playing when replaying
+    fn advance(&mut self) -> bool {
+        match &mut self.source {
+            Source::Live => true,
+            Source::Replay(replay) => replay.advance(),
+        }
+    }
+
+    /// Time between ticks; a playing replay follows the recorded intervals
+    fn tick_rate(&self, live: Duration) -> Duration {
+        match &self.source {
+            Source::Replay(replay) if replay.is_playing() => replay.next_gap().unwrap_or(live),
+            _ => live,
+        }
+    }
+
+    /// Apply a replay control key; returns false for keys it doesn't handle
+    fn replay_key(&mut self, code: KeyCode) -> bool {
+        let Source::Replay(replay) = &mut self.source else {
+            return false;
+        };
+        match code {
+            KeyCode::Char(' ') => replay.toggle_play(),
+            KeyCode::Char('.') => replay.step(1),
+            KeyCode::Char(',') => replay.step(-1),
+            KeyCode::Char(']') => replay.seek(10.0),
+            KeyCode::Char('[') => replay.seek(-10.0),
+            KeyCode::Char('+') | KeyCode::Char('=') => replay.faster(),
+            KeyCode::Char('-') => replay.slower(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn toggle_heatmap(&mut self) {
+        self.show_heatmap = !self.show_heatmap;
+        self.show_cpus = false;
+        self.cpu_detail = None;
+        self.heatmap_offset = 0;
+        // A capture has no sysfs tree, so replays fall back to per-CPU buckets
+        if self.show_heatmap && matches!(self.source, Source::Live) {
+            let cpus = self.sampler.current().cpus.iter().copied();
+            self.topology = CpuTopology::read(self.sampler.roots(), cpus);
+        }
+    }
+
+    fn next_grouping(&mut self) {
+        self.grouping = match self.grouping {
+            CpuGrouping::Cpu => CpuGrouping::Core,
+            CpuGrouping::Core => CpuGrouping::Socket,
+            CpuGrouping::Socket => CpuGrouping::Group(self.group_size),
+            CpuGrouping::Group(_) => CpuGrouping::Cpu,
+        };
+        self.heatmap_offset = 0;
+    }
+
+    /// Header segment showing how fresh the data is
+    fn update_label(&self) -> String {
+        match &self.source {
+            Source::Live => format!("Update: {}ms ago", self.last_update.elapsed().as_millis()),
+            Source::Replay(replay) => replay.status(),
+        }
+    }
+
+    /// Column label for the delta values under the current rate mode
+    fn delta_label(&self) -> &'static str {
+        match self.rate_mode {
+            RateMode::PerSecond => "Δ/s",
+            RateMode::PerInterval => "Δ",
+        }
+    }
+
+    /// Format a counter delta under the current rate mode
+    fn fmt_delta(&self, delta: u64) -> String {
+        match self.rate_mode {
+            RateMode::PerSecond => {
+                let rate = per_second(delta, self.sampler.elapsed());
+                if rate < 10.0 { format!("{:.1}", rate) } else { format!("{:.0}", rate) }
+            }
+            RateMode::PerInterval => delta.to_string(),
+        }
+    }
+
+    fn toggle_rate_mode(&mut self) {
+        self.rate_mode = match self.rate_mode {
+            RateMode::PerSecond => RateMode::PerInterval,
+            RateMode::PerInterval => RateMode::PerSecond,
+        };
+    }
+
+    /// Rebuild the visible rows from the sampler's deltas and keep the selection in range
+    pub fn apply_filter(&mut self) {
+        self.deltas = self.sampler.deltas().iter()
+            .filter_map(|d| Some((d.irq.clone(), self.shown_total(d)?)))
+            .collect();
+        self.sort_data();
+        self.selected_row = self.selected_row.min(self.deltas.len().saturating_sub(1));
+    }
+
+    /// Delta over the selected CPUs of a row that passes the category
+    /// toggles and the filter, `None` for a hidden row
+    fn shown_total(&self, delta: &IrqDelta) -> Option<u64> {
+        let data = self.sampler.current();
+        let stats = data.get(&delta.irq)?;
+        let total = self.filter.sum_selected(data, stats, &delta.per_cpu);
+        (!self.hidden_categories.contains(&stats.category) && self.filter.keeps(&delta.irq, stats, total))
+            .then_some(total)
+    }
+
+    /// Count columns of the selected CPUs with the delta of every per-CPU
+    /// row on them, whether or not the row is shown
+    fn cpu_totals(&self) -> Vec<(usize, u64)> {
+        let data = self.sampler.current();
+        let mut totals: Vec<(usize, u64)> = (0..data.cpus.len())
+            .filter(|idx| self.filter.keeps_column(data, *idx))
+            .map(|idx| (idx, 0))
+            .collect();
+        for delta in self.sampler.deltas() {
+            if data.get(&delta.irq).is_none_or(|s| s.is_global()) {
+                continue;
+            }
+            for (idx, total) in &mut totals {
+                *total += delta.per_cpu.get(*idx).copied().unwrap_or(0);
+            }
+        }
+        totals
+    }
+
+    /// Shown IRQs firing on count column `idx` with their delta there,
+    /// busiest first
+    fn cpu_irqs(&self, idx: usize) -> Vec<(&IrqId, u64)> {
+        let data = self.sampler.current();
+        let mut irqs: Vec<(&IrqId, u64)> = self.sampler.deltas().iter()
+            .filter(|d| data.get(&d.irq).is_some_and(|s| !s.is_global()) && self.shown_total(d).is_some())
+            .filter_map(|d| Some((&d.irq, *d.per_cpu.get(idx)?)))
+            .filter(|(_, delta)| *delta > 0)
+            .collect();
+        irqs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        irqs
+    }
+
+    fn toggle_cpus(&mut self) {
+        self.show_cpus = !self.show_cpus;
+        self.show_heatmap = false;
+        self.cpu_detail = None;
+    }
+
+    /// Handle navigation in the CPU list and its drill-down; returns false
+    /// for keys it doesn't handle
+    fn cpu_view_key(&mut self, code: KeyCode) -> bool {
+        let len = match self.cpu_detail {
+            Some((idx, _)) => self.cpu_irqs(idx).len(),
+            None => self.cpu_totals().len(),
+        };
+        let row = match &mut self.cpu_detail {
+            Some((_, row)) => row,
+            None => &mut self.cpu_row,
+        };
+        let max_row = len.saturating_sub(1);
+        match code {
+            KeyCode::Down => *row = (*row + 1).min(max_row),
+            KeyCode::Up => *row = row.saturating_sub(1),
+            KeyCode::PageDown => *row = (*row + 10).min(max_row),
+            KeyCode::PageUp => *row = row.saturating_sub(10),
+            KeyCode::Home => *row = 0,
+            KeyCode::End => *row = max_row,
+            KeyCode::Enter => match self.cpu_detail {
+                Some((idx, row)) => {
+                    if let Some((irq, _)) = self.cpu_irqs(idx).get(row) {
+                        let irq = (*irq).clone();
+                        self.open_detail(irq);
+                    }
+                }
+                None => {
+                    if let Some(&(idx, _)) = self.cpu_totals().get(self.cpu_row) {
+                        self.cpu_detail = Some((idx, 0));
+                    }
+                }
+            },
+            KeyCode::Esc if self.cpu_detail.is_some() => self.cpu_detail = None,
+            KeyCode::Esc => self.show_cpus = false,
+            _ => return false,
+        }
+        true
+    }
+
+    fn toggle_category(&mut self, category: IrqCategory) {
+        if !self.hidden_categories.remove(&category) {
+            self.hidden_categories.insert(category);
+        }
+        self.apply_filter();
+    }
+
+    fn open_search(&mut self) {
+        self.search_backup = self.filter.search.clone();
+        self.search_input = Some(self.filter.search.as_ref().map(Search::to_string).unwrap_or_default());
+        self.search_error = None;
+    }
+
+    /// Handle a key while the `/` prompt is open, filtering as the user types
+    fn search_key(&mut self, code: KeyCode) {
+        let Some(input) = &mut self.search_input else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                if self.search_error.is_none() {
+                    self.search_input = None;
+                }
+                return;
+            }
+            KeyCode::Esc => {
+                self.search_input = None;
+                self.search_error = None;
+                let backup = self.search_backup.take();
+                self.set_search(backup);
+                return;
+            }
+            _ => return,
+        }
+        let parsed = input.parse::<Search>().map(|search| (!input.trim().is_empty()).then_some(search));
+        match parsed {
+            Ok(search) => {
+                self.search_error = None;
+                self.set_search(search);
+            }
+            Err(e) => self.search_error = Some(e.to_string()),
+        }
+    }
+
+    /// Apply `search`, keeping the selection on the same IRQ while it is
+    /// still shown
+    fn set_search(&mut self, search: Option<Search>) {
+        let selected = self.deltas.get(self.selected_row).map(|(irq, _)| irq.clone());
+        self.filter.search = search;
+        self.apply_filter();
+        if let Some(row) = selected.and_then(|irq| self.deltas.iter().position(|(i, _)| *i == irq)) {
+            self.selected_row = row;
+        }
+    }
+
+    /// Move the selection to the next (or previous) shown row, wrapping
+    /// around; the detail view follows the selection, so this steps through
+    /// the filtered IRQs there
+    fn jump_row(&mut self, forward: bool) {
+        let len = self.deltas.len();
+        if len == 0 {
+            return;
+        }
+        self.selected_row = if forward { (self.selected_row + 1) % len } else { (self.selected_row + len - 1) % len };
+        if self.show_irq_detail {
+            let irq_name = self.deltas[self.selected_row].0.clone();
+            self.open_detail(irq_name);
+        }
+    }
+
+    fn sort_data(&mut self) {
+        let default_str = "N/A";
+        
+        match self.sort_by {
+            SortBy::Irq => self.deltas.sort_by(|a, b| a.0.cmp(&b.0)),
+            SortBy::Delta => self.deltas.sort_by_key(|d| std::cmp::Reverse(d.1)),
+            SortBy::Affinity => self.deltas.sort_by(|a, b| {
+                let a_aff = self.affinity_map.get(&a.0).map(|s| s.as_str()).unwrap_or(default_str);
+                let b_aff = self.affinity_map.get(&b.0).map(|s| s.as_str()).unwrap_or(default_str);
+                a_aff.cmp(b_aff)
+            }),
+            SortBy::EffectiveAffinity => self.deltas.sort_by(|a, b| {
+                let a_aff = self.effective_affinity_map.get(&a.0).map(|s| s.as_str()).unwrap_or(default_str);
+                let b_aff = self.effective_affinity_map.get(&b.0).map(|s| s.as_str()).unwrap_or(default_str);
+                a_aff.cmp(b_aff)
+            }),
+            SortBy::Chip => self.deltas.sort_by(|a, b| {
+                let a_chip = self.sampler.current().get(&a.0).map(|s| s.desc.chip.as_str()).unwrap_or(default_str);
+                let b_chip = self.sampler.current().get(&b.0).map(|s| s.desc.chip.as_str()).unwrap_or(default_str);
+                a_chip.cmp(b_chip)
+            }),
+            SortBy::Hwirq => self.deltas.sort_by_key(|d| self.sampler.current().get(&d.0).and_then(|s| s.desc.hwirq)),
+            SortBy::Trigger => self.deltas.sort_by(|a, b| {
+                let a_trig = self.sampler.current().get(&a.0).map(|s| s.desc.trigger.as_str()).unwrap_or(default_str);
+                let b_trig = self.sampler.current().get(&b.0).map(|s| s.desc.trigger.as_str()).unwrap_or(default_str);
+                a_trig.cmp(b_trig)
+            }),
+            SortBy::Device => self.deltas.sort_by_cached_key(|d| {
+                self.sampler.current().get(&d.0).map(|s| s.device()).unwrap_or_default()
+            }),
+        }
+    }
+
+    fn next_sort(&mut self) {
+        self.sort_by = match self.sort_by {
+            SortBy::Irq => SortBy::Delta,
+            SortBy::Delta => SortBy::Affinity,
+            SortBy::Affinity => SortBy::EffectiveAffinity,
+            SortBy::EffectiveAffinity => SortBy::Chip,
+            SortBy::Chip => SortBy::Hwirq,
+            SortBy::Hwirq => SortBy::Trigger,
+            SortBy::Trigger => SortBy::Device,
+            SortBy::Device => SortBy::Irq,
+        };
+    }
+}
+
+pub fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    tick_rate: Duration,
+) -> Result<()> {
+    let mut last_tick = Instant::now();
+    
+    loop {
+        terminal.draw(|f| ui(f, &mut app))?;
+
+        let tick_rate = app.tick_rate(tick_rate);
+        let timeout = tick_rate
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if crossterm::event::poll(timeout)? && let Event::Key(key) = event::read()? {
+            if app.search_input.is_some() {
+                app.search_key(key.code);
+                continue;
+            }
+            if app.show_cpus && !app.show_irq_detail && !app.show_help && app.cpu_view_key(key.code) {
+                continue;
+            }
+            if app.replay_key(key.code) {
+                app.update_data()?;
+                app.apply_filter();
+                last_tick = Instant::now();
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') => {
+                    app.running = false;
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.running = false;
+                }
+                KeyCode::Down => {
+                    let max_row = app.deltas.len().saturating_sub(1);
+                    app.selected_row = (app.selected_row + 1).min(max_row);
+                }
+                KeyCode::Up => {
+                    app.selected_row = app.selected_row.saturating_sub(1);
+                }
+                KeyCode::PageDown => {
+                    let max_row = app.deltas.len().saturating_sub(1);
+                    app.selected_row = (app.selected_row + 10).min(max_row);
+                }
+                KeyCode::PageUp => {
+                    app.selected_row = app.selected_row.saturating_sub(10);
+                }
+                KeyCode::Home => {
+                    app.selected_row = 0;
+                }
+                KeyCode::End => {
+                    app.selected_row = app.deltas.len().saturating_sub(1);
+                }
+                KeyCode::Tab => {
+                    app.next_sort();
+                    app.sort_data();
+                }
+                KeyCode::Char('h') | KeyCode::Char('H') => {
+                    app.show_help = !app.show_help;
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') => app.toggle_rate_mode(),
+                KeyCode::Char('1') => app.toggle_category(IrqCategory::Device),
+                KeyCode::Char('2') => app.toggle_category(IrqCategory::System),
+                KeyCode::Char('3') => app.toggle_category(IrqCategory::Error),
+                KeyCode::Char('m') | KeyCode::Char('M') if !app.show_irq_detail => app.toggle_heatmap(),
+                KeyCode::Char('c') | KeyCode::Char('C') if !app.show_irq_detail => app.toggle_cpus(),
+                KeyCode::Char('g') | KeyCode::Char('G') if app.show_heatmap => app.next_grouping(),
+                KeyCode::Left if app.show_heatmap => {
+                    app.heatmap_offset = app.heatmap_offset.saturating_sub(1);
+                }
+                KeyCode::Right if app.show_heatmap => {
+                    app.heatmap_offset += 1;
+                }
+                KeyCode::Enter if !app.show_heatmap && app.selected_row < app.deltas.len() => {
+                    let irq_name = app.deltas[app.selected_row].0.clone();
+                    app.open_detail(irq_name);
+                }
+                KeyCode::Char('/') => app.open_search(),
+                KeyCode::Char('n') => app.jump_row(true),
+                KeyCode::Char('N') => app.jump_row(false),
+                KeyCode::Esc if app.show_heatmap && !app.show_irq_detail => app.show_heatmap = false,
+                KeyCode::Esc if !app.show_irq_detail && app.filter.search.is_some() => app.set_search(None),
+                KeyCode::Esc => {
+                    app.show_irq_detail = false;
+                    app.detail_irq_name = None;
+                    app.detail_scroll_offset = 0;
+                }
+                KeyCode::Char('j') | KeyCode::Char('J') if app.show_irq_detail => {
+                    app.detail_scroll_offset += 1;
+                }
+                KeyCode::Char('k') | KeyCode::Char('K') if app.show_irq_detail => {
+                    app.detail_scroll_offset = app.detail_scroll_offset.saturating_sub(1);
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') if app.show_irq_detail => {
+                    app.detail_scroll_offset += 10;
+                }
+                KeyCode::Char('u') | KeyCode::Char('U') if app.show_irq_detail => {
+                    app.detail_scroll_offset = app.detail_scroll_offset.saturating_sub(10);
+                }
+                _ => {}
+            }
+        }
+
+        if last_tick.elapsed() >= tick_rate {
+            if app.advance() {
+                app.update_data()?;
+                app.apply_filter();
+            }
+            last_tick = Instant::now();
+        }
+
+        if !app.running {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    draw_view(f, app);
+
+    // The `/` prompt covers the bottom line of whichever view is open
+    if let Some(input) = &app.search_input {
+        let size = f.size();
+        let area = Rect { y: size.bottom().saturating_sub(1), height: size.height.min(1), ..size };
+        let prompt = match &app.search_error {
+            Some(e) => Paragraph::new(format!("/{}█  ({})", input, e)).style(Style::default().fg(Color::Red)),
+            None => Paragraph::new(format!("/{}█  (Enter: Apply | Esc: Cancel)", input))
+                .style(Style::default().fg(Color::Yellow)),
+        };
+        f.render_widget(Clear, area);
+        f.render_widget(prompt, area);
+    }
+}
+
+fn draw_view(f: &mut Frame, app: &mut App) {
+    let size = f.size();
+    
+    if app.show_irq_detail {
+        show_irq_detail(f, app);
+        return;
+    }
+    
+    if app.show_help {
+        show_help(f);
+        return;
+    }
+
+    if app.show_heatmap {
+        show_heatmap(f, app);
+        return;
+    }
+
+    if let Some((idx, _)) = app.cpu_detail {
+        show_cpu_detail(f, app, idx);
+        return;
+    }
+
+    if app.show_cpus {
+        show_cpus(f, app);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(size);
+
+    // Header
+    let shown: Vec<&str> = IrqCategory::ALL.iter()
+        .filter(|c| !app.hidden_categories.contains(c))
+        .map(|c| c.label())
+        .collect();
+    let header = Paragraph::new(format!(
+        "IRQTop v0.1.0 - Real-time Interrupt Statistics | {} | Interval: {}ms | Sort: {} | Show: {}{} | Press 'h' for help",
+        app.update_label(),
+        app.sampler.elapsed().as_millis(),
+        match app.sort_by {
+            SortBy::Irq => "IRQ",
+            SortBy::Delta => "Delta",
+            SortBy::Affinity => "Affinity",
+            SortBy::EffectiveAffinity => "Eff. Affinity",
+            SortBy::Chip => "Chip",
+            SortBy::Hwirq => "HWIRQ",
+            SortBy::Trigger => "Trigger",
+            SortBy::Device => "Device",
+        },
+        if shown.is_empty() { "none".to_string() } else { shown.join("+") },
+        if app.filter.is_empty() {
+            String::new()
+        } else {
+            format!(" | Filter: {} ({} rows)", app.filter, app.deltas.len())
+        }
+    ))
+    .style(Style::default().fg(Color::Cyan))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    // Table
+    let selected_style = Style::default().add_modifier(Modifier::REVERSED);
+    let normal_style = Style::default().bg(Color::DarkGray);
+    
+    let header_cells = vec![
+        Cell::from("IRQ"),
+        Cell::from(app.delta_label()),
+        Cell::from("History"),
+        Cell::from("Affinity"),
+        Cell::from("Eff. Affinity"),
+        Cell::from("Chip"),
+        Cell::from("HWIRQ"),
+        Cell::from("Trigger"),
+        Cell::from("Device"),
+    ];
+    let header = Row::new(header_cells)
+        .style(Style::default().fg(Color::Yellow))
+        .height(1)
+        .bottom_margin(1);
+
+    let default_str = "N/A";
+    let rows: Vec<Row> = app
+        .deltas
+        .iter()
+        .enumerate()
+        .map(|(i, (irq, delta))| {
+            let stats = app.sampler.current().get(irq).unwrap();
+            let affinity = app.affinity_map.get(irq).map(|s| s.as_str()).unwrap_or(default_str);
+            let effective_affinity = app.effective_affinity_map.get(irq).map(|s| s.as_str()).unwrap_or(default_str);
+            
+            let cells = vec![
+                Cell::from(irq.to_string()),
+                Cell::from(app.fmt_delta(*delta)),
+                Cell::from(sparkline(app.history.irq(irq), SPARKLINE_WIDTH)),
+                Cell::from(affinity),
+                Cell::from(effective_affinity),
+                Cell::from(stats.desc.chip.as_str()),
+                Cell::from(stats.desc.hwirq.map(|h| h.to_string()).unwrap_or_default()),
+                Cell::from(stats.desc.trigger.as_str()),
+                Cell::from(stats.device()),
+            ];
+            
+            if i == app.selected_row {
+                Row::new(cells).style(selected_style)
+            } else {
+                Row::new(cells).style(normal_style)
+            }
+        })
+        .collect();
+
+    let table = Table::new(rows, &[
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(SPARKLINE_WIDTH as u16),
+        Constraint::Length(12),
+        Constraint::Length(15),
+        Constraint::Length(16),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Percentage(40),
+    ])
+        .header(header)
+        .block(Block::default().borders(Borders::ALL));
+
+    f.render_widget(table, chunks[1]);
+
+    // Footer
+    let footer = Paragraph::new(match app.source {
+        Source::Live => "q: Quit | ↑/↓: Navigate | Tab: Sort | Enter: Detail | c: CPUs | m: Heatmap | /: Search | n/N: Next/Prev Row | r: Rate | 1/2/3: Device/System/Error | h: Help",
+        Source::Replay(_) => "q: Quit | ↑/↓: Navigate | Tab: Sort | Enter: Detail | c: CPUs | m: Heatmap | /: Search | Space: Play/Pause | ,/.: Step | [/]: Seek | -/+: Speed | h: Help",
+    })
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(footer, chunks[2]);
+}
+
+fn show_help(f: &mut Frame) {
+    let help_text = "IRQTop Help\n\nNavigation:\n  ↑/↓     - Move selection up/down\n  PageUp  - Move up 10 rows\n  PageDown- Move down 10 rows\n  Home    - Go to first row\n  End     - Go to last row\n\nSorting:\n  Tab     - Cycle through sort options\n\nDisplay:\n  r       - Toggle per-second / per-interval deltas\n\nSearch:\n  /       - Filter rows, like --filter: text, re:REGEX, irq:LIST, dev:GLOB, !GLOB\n  n/N     - Next/previous shown row, wrapping; steps IRQs in detail\n  Esc     - Clear the filter\n\nCategories:\n  1       - Show/hide device IRQs\n  2       - Show/hide system rows (NMI, LOC, RES, ...)\n  3       - Show/hide error counters (ERR, MIS)\n\nDetail View:\n  Enter   - View selected IRQ details\n  Esc     - Return to main view\n  j/k     - Scroll down/up in detail view\n  d/u     - Scroll page down/up in detail view\n\nCPU View:\n  c       - Show/hide total rates per CPU\n  Enter   - List the IRQs firing on the selected CPU\n\nHeatmap:\n  m       - Show/hide the CPU × IRQ heatmap\n  g       - Group columns by CPU, core, socket or N CPUs\n  ←/→     - Scroll columns\n\nReplay:\n  Space   - Play/pause\n  ,/.     - Step one frame back/forward\n  [/]     - Seek 10 seconds back/forward\n  -/+     - Halve/double playback speed\n\nOther:\n  h       - Toggle this help screen\n  q       - Quit\n  Ctrl+C  - Force quit\n\nPress any key to close this help...";
+
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .title("Help")
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::Yellow)),
+        );
+
+    let area = centered_rect(60, 80, f.size());
+    f.render_widget(help, area);
+}
+
+fn show_irq_detail(f: &mut Frame, app: &mut App) {
+    let size = f.size();
+    
+    if let Some(irq_name) = &app.detail_irq_name
+        && let Some(stats) = app.sampler.current().get(irq_name)
+    {
+        // Totals and per-CPU values come from the same delta
+        let delta = app.sampler.delta(irq_name);
+        let data = app.sampler.current();
+        let delta_value = delta.map_or(0, |d| app.filter.sum_selected(data, stats, &d.per_cpu));
+        // Count columns of the CPUs the filter keeps
+        let columns: Vec<usize> = (0..stats.counts.len())
+            .filter(|idx| app.filter.keeps_column(data, *idx))
+            .collect();
+        
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(10),
+                Constraint::Min(0),
+                Constraint::Length(2),
+            ])
+            .split(size);
+
+        // Header
+        let header = Paragraph::new(format!(
+            "IRQ Detail: {} ({}) | Total {}: {} | Total CPUs: {} | Press Esc to return",
+            irq_name,
+            stats.name,
+            app.delta_label(),
+            app.fmt_delta(delta_value),
+            columns.len()
+        ))
+        .style(Style::default().fg(Color::Cyan))
+        .block(Block::default().borders(Borders::ALL));
+        f.render_widget(header, chunks[0]);
+
+        render_history_chart(f, chunks[1], app.history.times(), app.history.irq(irq_name));
+
+        // ERR/MIS have no per-CPU columns to break down
+        if stats.is_global() {
+            let body = Paragraph::new(format!(
+                "{} is a system-wide error counter.\n\nTotal: {}\n{}: {}",
+                irq_name,
+                stats.total(),
+                app.delta_label(),
+                app.fmt_delta(delta_value)
+            ))
+            .block(Block::default().borders(Borders::ALL));
+            f.render_widget(body, chunks[2]);
+            return;
+        }
+
+        // CPU stats table
+        let normal_style = Style::default().bg(Color::DarkGray);
+        
+        let header_cells = vec![
+            Cell::from("CPU"),
+            Cell::from(app.delta_label()),
+            Cell::from("CPU"),
+            Cell::from(app.delta_label()),
+            Cell::from("CPU"),
+            Cell::from(app.delta_label()),
+            Cell::from("CPU"),
+            Cell::from(app.delta_label()),
+        ];
+        let header = Row::new(header_cells)
+            .style(Style::default().fg(Color::Yellow))
+            .height(1)
+            .bottom_margin(1);
+
+        // Calculate visible rows and columns
+        let available_height = chunks[2].height.saturating_sub(2) as usize;
+        let rows_per_column = available_height.saturating_sub(1);
+        let total_cpus = columns.len();
+        let cpus_per_row = 4;  // 4 CPUs per row
+        
+        let visible_rows = rows_per_column.min(total_cpus.div_ceil(cpus_per_row));
+        let max_scroll = total_cpus.div_ceil(cpus_per_row);
+        let max_scroll = max_scroll.saturating_sub(visible_rows);
+        
+        // Clamp scroll offset
+        app.detail_scroll_offset = app.detail_scroll_offset.min(max_scroll);
+
+        // Get per-CPU deltas for this IRQ
+        let per_cpu_deltas = delta.map_or(&[][..], |d| d.per_cpu.as_slice());
+        
+        // Create rows for visible data
+        let mut rows = Vec::new();
+        for row_idx in 0..visible_rows {
+            let start_cpu = (app.detail_scroll_offset + row_idx) * cpus_per_row;
+            if start_cpu >= total_cpus {
+                break;
+            }
+            
+            let mut cells = Vec::new();
+            for col in 0..cpus_per_row {
+                if let Some(&cpu_idx) = columns.get(start_cpu + col)
+                    && cpu_idx < per_cpu_deltas.len()
+                {
+                    cells.push(Cell::from(format!("CPU{}", app.sampler.current().cpu_id(cpu_idx))));
+                    cells.push(Cell::from(app.fmt_delta(per_cpu_deltas[cpu_idx])));
+                } else {
+                    cells.push(Cell::from(""));
+                    cells.push(Cell::from(""));
+                }
+            }
+            
+            rows.push(Row::new(cells).style(normal_style));
+        }
+
+        let table = Table::new(rows, &[
+            Constraint::Length(6),  // CPU label
+            Constraint::Length(12), // Delta
+            Constraint::Length(6),  // CPU label
+            Constraint::Length(12), // Delta
+            Constraint::Length(6),  // CPU label
+            Constraint::Length(12), // Delta
+            Constraint::Length(6),  // CPU label
+            Constraint::Length(12), // Delta
+        ])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL));
+
+        f.render_widget(table, chunks[2]);
+
+        // Footer with navigation help
+        let footer = Paragraph::new(format!(
+            "j/k: Scroll down/up | d/u: Page down/up | Scroll: {}/{} | Esc: Return",
+            app.detail_scroll_offset + 1,
+            max_scroll + 1
+        ))
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+        f.render_widget(footer, chunks[3]);
+    }
+}
+
+/// Total interrupt rate of each selected CPU
+fn show_cpus(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let data = app.sampler.current();
+    let totals = app.cpu_totals();
+    app.cpu_row = app.cpu_row.min(totals.len().saturating_sub(1));
+
+    let header = Paragraph::new(format!(
+        "Per-CPU Totals | {} | Total {}: {} | CPUs: {} | Press Esc to return",
+        app.update_label(),
+        app.delta_label(),
+        app.fmt_delta(totals.iter().map(|(_, total)| total).sum()),
+        totals.len()
+    ))
+    .style(Style::default().fg(Color::Cyan))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let header = Row::new(vec![
+        Cell::from("CPU"),
+        Cell::from(app.delta_label()),
+        Cell::from("History"),
+        Cell::from("IRQs"),
+        Cell::from("Busiest"),
+    ])
+        .style(Style::default().fg(Color::Yellow))
+        .height(1)
+        .bottom_margin(1);
+
+    // Keep the selection on screen
+    let visible = chunks[1].height.saturating_sub(4) as usize;
+    let first = (app.cpu_row + 1).saturating_sub(visible);
+    let rows: Vec<Row> = totals.iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(i, &(idx, total))| {
+            let irqs = app.cpu_irqs(idx);
+            let busiest = irqs.first().map_or(String::new(), |(irq, delta)| {
+                let device = data.get(irq).map(|s| s.device()).unwrap_or_default();
+                format!("{} {} ({:.0}%)", irq, device, *delta as f64 * 100.0 / total as f64)
+            });
+            let cells = vec![
+                Cell::from(format!("CPU{}", data.cpu_id(idx))),
+                Cell::from(app.fmt_delta(total)),
+                Cell::from(sparkline(app.history.cpu(data.cpu_id(idx)), SPARKLINE_WIDTH)),
+                Cell::from(irqs.len().to_string()),
+                Cell::from(busiest),
+            ];
+            let style = if i == app.cpu_row {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default().bg(Color::DarkGray)
+            };
+            Row::new(cells).style(style)
+        })
+        .collect();
+
+    let table = Table::new(rows, &[
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(SPARKLINE_WIDTH as u16),
+        Constraint::Length(6),
+        Constraint::Percentage(60),
+    ])
+        .header(header)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(table, chunks[1]);
+
+    let footer = Paragraph::new("↑/↓: Navigate | Enter: IRQs on CPU | r: Rate | c/Esc: Return")
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(footer, chunks[2]);
+}
+
+/// Shown IRQs firing on the CPU in count column `idx`, busiest first
+fn show_cpu_detail(f: &mut Frame, app: &mut App, idx: usize) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let data = app.sampler.current();
+    let irqs = app.cpu_irqs(idx);
+    let total = app.cpu_totals().iter().find(|(i, _)| *i == idx).map_or(0, |(_, total)| *total);
+    let selected = app.cpu_detail.map_or(0, |(_, row)| row).min(irqs.len().saturating_sub(1));
+
+    let header = Paragraph::new(format!(
+        "CPU{} | {} | Total {}: {} | IRQs: {} | Press Esc to return",
+        data.cpu_id(idx),
+        app.update_label(),
+        app.delta_label(),
+        app.fmt_delta(total),
+        irqs.len()
+    ))
+    .style(Style::default().fg(Color::Cyan))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let header = Row::new(vec![
+        Cell::from("IRQ"),
+        Cell::from(app.delta_label()),
+        Cell::from("Share"),
+        Cell::from("Affinity"),
+        Cell::from("Eff. Affinity"),
+        Cell::from("Device"),
+    ])
+        .style(Style::default().fg(Color::Yellow))
+        .height(1)
+        .bottom_margin(1);
+
+    let default_str = "N/A";
+    let visible = chunks[1].height.saturating_sub(4) as usize;
+    let first = (selected + 1).saturating_sub(visible);
+    let rows: Vec<Row> = irqs.iter()
+        .enumerate()
+        .skip(first)
+        .take(visible)
+        .map(|(i, (irq, delta))| {
+            let cells = vec![
+                Cell::from(irq.to_string()),
+                Cell::from(app.fmt_delta(*delta)),
+                Cell::from(format!("{:.1}%", *delta as f64 * 100.0 / total.max(1) as f64)),
+                Cell::from(app.affinity_map.get(*irq).map(|s| s.as_str()).unwrap_or(default_str)),
+                Cell::from(app.effective_affinity_map.get(*irq).map(|s| s.as_str()).unwrap_or(default_str)),
+                Cell::from(data.get(irq).map(|s| s.device()).unwrap_or_default()),
+            ];
+            let style = if i == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default().bg(Color::DarkGray)
+            };
+            Row::new(cells).style(style)
+        })
+        .collect();
+
+    let table = Table::new(rows, &[
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(15),
+        Constraint::Percentage(50),
+    ])
+        .header(header)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(table, chunks[1]);
+
+    let footer = Paragraph::new("↑/↓: Navigate | Enter: IRQ Detail | r: Rate | Esc: Return")
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(footer, chunks[2]);
+}
+
+/// Colored grid of the busiest IRQs' rates by CPU bucket
+fn show_heatmap(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.size());
+
+    let data = app.sampler.current();
+    let columns: Vec<usize> = (0..data.cpus.len())
+        .filter(|idx| app.filter.keeps_column(data, *idx))
+        .collect();
+    let buckets = app.topology.buckets(data, &columns, app.grouping);
+
+    // As many of the busiest per-CPU rows as fit, below the label row
+    let mut irqs: Vec<&(IrqId, u64)> = app.deltas.iter()
+        .filter(|(irq, _)| data.get(irq).is_some_and(|s| !s.is_global()))
+        .collect();
+    irqs.sort_by_key(|(_, delta)| std::cmp::Reverse(*delta));
+    irqs.truncate(chunks[1].height.saturating_sub(4) as usize);
+
+    let elapsed = app.sampler.elapsed();
+    let rates: Vec<Vec<f64>> = irqs.iter()
+        .map(|(irq, _)| {
+            let per_cpu = app.sampler.delta(irq).map_or(&[][..], |d| d.per_cpu.as_slice());
+            buckets.iter()
+                .map(|b| per_second(b.columns.iter().filter_map(|&idx| per_cpu.get(idx)).sum(), elapsed))
+                .collect()
+        })
+        .collect();
+    let max = rates.iter().flatten().copied().fold(0.0, f64::max);
+
+    // Fit as many bucket columns as the width allows and scroll the rest
+    let cell_width = buckets.iter().map(|b| b.label.len()).max().unwrap_or(0).max(2);
+    let visible = (chunks[1].width.saturating_sub(2 + 9) as usize / (cell_width + 1)).max(1);
+    app.heatmap_offset = app.heatmap_offset.min(buckets.len().saturating_sub(visible));
+    let shown = app.heatmap_offset..(app.heatmap_offset + visible).min(buckets.len());
+
+    let header = Paragraph::new(format!(
+        "CPU × IRQ Heatmap | {} | Group: {} ({} columns) | Scale: 0 - {:.0}/s (log) | Press Esc to return",
+        app.update_label(),
+        match app.grouping {
+            CpuGrouping::Cpu => "CPU".to_string(),
+            CpuGrouping::Core => "core".to_string(),
+            CpuGrouping::Socket => "socket".to_string(),
+            CpuGrouping::Group(n) => format!("{} CPUs", n),
+        },
+        buckets.len(),
+        max
+    ))
+    .style(Style::default().fg(Color::Cyan))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let header_cells = std::iter::once(Cell::from("IRQ"))
+        .chain(buckets[shown.clone()].iter().map(|b| Cell::from(b.label.as_str())));
+    let header = Row::new(header_cells)
+        .style(Style::default().fg(Color::Yellow))
+        .height(1)
+        .bottom_margin(1);
+
+    let rows: Vec<Row> = irqs.iter()
+        .zip(&rates)
+        .map(|((irq, _), rates)| {
+            // Buckets the IRQ may be routed to; unknown affinity allows all
+            let allowed = app.effective_affinity_map.get(irq)
+                .or_else(|| app.affinity_map.get(irq))
+                .and_then(|list| list.parse::<CpuList>().ok());
+            let cells = shown.clone().map(|i| {
+                let expected = allowed.as_ref().is_none_or(|list| {
+                    buckets[i].columns.iter().any(|&idx| list.contains(data.cpu_id(idx)))
+                });
+                let rate = rates[i];
+                match (rate > 0.0, expected) {
+                    (true, _) => Cell::from(if expected { "" } else { "!" })
+                        .style(Style::default().fg(Color::White).bg(heat_color(rate, max))),
+                    (false, true) => Cell::from("·").style(Style::default().fg(Color::DarkGray)),
+                    (false, false) => Cell::from(""),
+                }
+            });
+            Row::new(std::iter::once(Cell::from(irq.to_string())).chain(cells))
+        })
+        .collect();
+
+    let widths: Vec<Constraint> = std::iter::once(Constraint::Length(8))
+        .chain(shown.clone().map(|_| Constraint::Length(cell_width as u16)))
+        .collect();
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(table, chunks[1]);
+
+    let footer = Paragraph::new(format!(
+        "g: Group | ←/→: Scroll columns {}-{}/{} | ! active outside affinity · allowed but idle | m/Esc: Return",
+        shown.start + 1,
+        shown.end,
+        buckets.len()
+    ))
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center);
+    f.render_widget(footer, chunks[2]);
+}
+
+/// Heat ramp color for `rate` on a log scale up to `max`
+fn heat_color(rate: f64, max: f64) -> Color {
+    let level = if max > 0.0 { rate.ln_1p() / max.ln_1p() } else { 0.0 };
+    let idx = (level * (HEAT_COLORS.len() - 1) as f64).round() as usize;
+    Color::Indexed(HEAT_COLORS[idx.min(HEAT_COLORS.len() - 1)])
+}
+
+/// Text sparkline of the last `width` values, scaled to their maximum and
+/// right-aligned so the newest sample is always in the last column
+fn sparkline(series: Option<&VecDeque<f64>>, width: usize) -> String {
+    let Some(series) = series else {
+        return String::new();
+    };
+    let recent = series.iter().copied().skip(series.len().saturating_sub(width));
+    let max = recent.clone().fold(0.0, f64::max);
+    let mut line = " ".repeat(width.saturating_sub(series.len()));
+    line.extend(recent.map(|rate| {
+        if rate <= 0.0 || max <= 0.0 {
+            ' '
+        } else {
+            let level = (rate / max * (SPARKLINE_BARS.len() - 1) as f64).round() as usize;
+            SPARKLINE_BARS[level.min(SPARKLINE_BARS.len() - 1)]
+        }
+    }));
+    line
+}
+
+/// Line chart of a rate series against seconds before the newest sample
+fn render_history_chart(f: &mut Frame, area: Rect, times: &VecDeque<f64>, series: Option<&VecDeque<f64>>) {
+    let block = Block::default().title("Rate history (/s)").borders(Borders::ALL);
+    let Some(series) = series.filter(|s| s.len() > 1) else {
+        let waiting = Paragraph::new("Collecting samples...")
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center)
+            .block(block);
+        f.render_widget(waiting, area);
+        return;
+    };
+    // Series end on the newest slot, which may be shorter than `times`
+    let times = times.iter().skip(times.len().saturating_sub(series.len()));
+    let now = times.clone().last().copied().unwrap_or_default();
+    let points: Vec<(f64, f64)> = times.zip(series).map(|(t, rate)| (t - now, *rate)).collect();
+    let span = -points[0].0;
+    let max = points.iter().map(|p| p.1).fold(0.0, f64::max).max(1.0);
+
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([-span, 0.0])
+                .labels(vec![Span::raw(format!("-{:.0}s", span)), Span::raw("now")]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", max))]),
+        );
+    f.render_widget(chart, area);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
@@ -0,0 +1,79 @@
+//! Steady-state sampling must not touch the heap. Lives in its own test
+//! binary so the counting allocator only sees this test.
+
+use irqtop_rs::{Roots, Sampler};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fs;
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn interrupts(tick: u64) -> String {
+    format!(
+        "           CPU0       CPU1       CPU3
+  0:         44          0          0   IO-APIC   2-edge      timer
+ 24:     {:>6}        200          3   IR-PCI-MSI 524288-edge      nvme0q1
+NMI:          1          2          3   Non-maskable interrupts
+LOC:     {:>6}     654321       1000   Local timer interrupts
+ERR:          7
+MIS:          0
+",
+        1000 + tick * 7,
+        123456 + tick * 100
+    )
+}
+
+#[test]
+fn steady_state_sampling_does_not_allocate() {
+    let dir = std::env::temp_dir().join(format!("irqtop-rs-alloc-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("interrupts");
+    fs::write(&path, interrupts(0)).unwrap();
+
+    let mut sampler = Sampler::new(Roots::new(&dir, "/nonexistent"));
+    // Warm up: open the reader, size the snapshots and the delta list
+    let before = ALLOCATIONS.with(Cell::get);
+    for _ in 0..3 {
+        sampler.sample().unwrap();
+    }
+    assert!(ALLOCATIONS.with(Cell::get) > before, "allocator is not counting");
+
+    let mut allocations = 0;
+    for tick in 1..=100 {
+        // Same length, so the open file's contents are replaced in place
+        let content = interrupts(tick);
+        fs::write(&path, &content).unwrap();
+
+        let before = ALLOCATIONS.with(Cell::get);
+        sampler.sample().unwrap();
+        allocations += ALLOCATIONS.with(Cell::get) - before;
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(allocations, 0);
+    assert_eq!(sampler.deltas().len(), 6);
+    assert_eq!(sampler.delta(&24.into()).unwrap().total, 7);
+}